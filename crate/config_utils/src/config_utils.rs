@@ -1,13 +1,21 @@
 use std::{
     env,
+    ffi::OsString,
     fmt::Debug,
-    fs::{self},
-    path::PathBuf,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicU64, Ordering},
 };
 
 use serde::{Serialize, de::DeserializeOwned};
 use tracing::{info, trace};
 
+#[cfg(feature = "encryption")]
+use crate::encryption::{decrypt, encrypt};
+#[cfg(feature = "watch")]
+use crate::watch::{ConfigWatcher, watch_file};
 use crate::{
     config_bail,
     error::{ConfigUtilsError, result::ConfigUtilsResultHelper},
@@ -46,6 +54,26 @@ pub fn get_default_conf_path(default_local_path: &str) -> Result<PathBuf, Config
         .map(|home| home.join(default_local_path))
 }
 
+/// Returns the configuration path following the XDG base directory
+/// specification: `$XDG_CONFIG_HOME/<xdg_local_path>` when the variable is
+/// set to an absolute path, `~/.config/<xdg_local_path>` otherwise.
+///
+/// This only applies to Unix systems other than macOS: elsewhere, this is
+/// the same as [`get_default_conf_path`].
+pub fn get_xdg_conf_path(xdg_local_path: &str) -> Result<PathBuf, ConfigUtilsError> {
+    if cfg!(all(unix, not(target_os = "macos"))) {
+        if let Some(config_home) = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+        {
+            return Ok(config_home.join(xdg_local_path));
+        }
+        get_default_conf_path(".config").map(|config_home| config_home.join(xdg_local_path))
+    } else {
+        get_default_conf_path(xdg_local_path)
+    }
+}
+
 pub fn location(
     conf: Option<PathBuf>,
     env_var_name: &str,
@@ -114,12 +142,281 @@ pub fn location(
 }
 
 /// Supported file formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum SupportedFile {
     Toml,
     Json,
+    /// Requires the `yaml` feature, a `ConfigUtilsError::NotSupported` error
+    /// is returned otherwise
+    Yaml,
+}
+
+impl SupportedFile {
+    /// Guess the format of a configuration file from its extension:
+    /// `.toml`, `.json`, `.yaml` or `.yml`
+    pub fn from_extension(conf_path: &str) -> Result<Self, ConfigUtilsError> {
+        let extension = Path::new(conf_path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("toml") => Ok(Self::Toml),
+            Some("json") => Ok(Self::Json),
+            Some("yaml" | "yml") => Ok(Self::Yaml),
+            _ => Err(ConfigUtilsError::NotSupported(format!(
+                "unable to guess the format of the configuration file {conf_path:?}: expected a \
+                 .toml, .json, .yaml or .yml extension"
+            ))),
+        }
+    }
+
+    fn serialize<T: Serialize + Debug>(self, conf: &T) -> Result<String, ConfigUtilsError> {
+        match self {
+            Self::Json => serde_json::to_string_pretty(conf)
+                .with_context(|| format!("Unable to serialize default configuration {conf:?}")),
+            Self::Toml => toml::to_string_pretty(conf)
+                .with_context(|| format!("Unable to serialize default configuration {conf:?}")),
+            #[cfg(feature = "yaml")]
+            Self::Yaml => serde_yaml::to_string(conf)
+                .with_context(|| format!("Unable to serialize default configuration {conf:?}")),
+            #[cfg(not(feature = "yaml"))]
+            Self::Yaml => Err(yaml_not_supported()),
+        }
+    }
+
+    fn deserialize<T: DeserializeOwned>(self, content: &str) -> Result<T, ConfigUtilsError> {
+        match self {
+            Self::Json => serde_json::from_str(content).context("Error while parsing JSON"),
+            Self::Toml => toml::from_str(content).context("Error while parsing TOML"),
+            #[cfg(feature = "yaml")]
+            Self::Yaml => serde_yaml::from_str(content).context("Error while parsing YAML"),
+            #[cfg(not(feature = "yaml"))]
+            Self::Yaml => Err(yaml_not_supported()),
+        }
+    }
+}
+
+#[cfg(not(feature = "yaml"))]
+fn yaml_not_supported() -> ConfigUtilsError {
+    ConfigUtilsError::NotSupported(
+        "YAML configuration files require the yaml feature of cosmian_config_utils".to_owned(),
+    )
+}
+
+/// Distinguishes the temporary files of concurrent writes in the process
+static TMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Write a file through a temporary file in the same directory renamed over
+/// it, so that the file is never left truncated if the process dies while
+/// writing.
+///
+/// The rename is atomic on the same file system and replaces an existing file
+/// on Windows too. A symbolic link is followed, so the file it points to is
+/// replaced and the link is kept. On Unix, the directory is synced after the
+/// rename so that the new file survives a crash.
+///
+/// When `owner_only` is set, the file is created readable and writable by its
+/// owner only (mode `600`) on Unix. Otherwise an existing file keeps its
+/// permissions.
+fn write_atomically(path: &Path, content: &[u8], owner_only: bool) -> io::Result<()> {
+    let path = if fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_symlink()) {
+        fs::canonicalize(path)?
+    } else {
+        path.to_path_buf()
+    };
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file path"))?;
+    let mut tmp_name = OsString::from(".");
+    tmp_name.push(file_name);
+    tmp_name.push(format!(
+        ".{}.{}.tmp",
+        process::id(),
+        TMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let tmp_path = path.with_file_name(tmp_name);
+    let permissions = if owner_only {
+        None
+    } else {
+        fs::metadata(&path)
+            .ok()
+            .map(|metadata| metadata.permissions())
+    };
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    if owner_only {
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    }
+
+    let result = options
+        .open(&tmp_path)
+        .and_then(|mut file| {
+            file.write_all(content)?;
+            if let Some(permissions) = permissions {
+                file.set_permissions(permissions)?;
+            }
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&tmp_path, &path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result?;
+
+    #[cfg(unix)]
+    {
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        File::open(directory)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Read and deserialize an existing configuration file in the given format
+pub(crate) fn read_file<C>(
+    conf_path: &str,
+    supported_file: SupportedFile,
+) -> Result<C, ConfigUtilsError>
+where
+    C: DeserializeOwned,
+{
+    let file = File::open(conf_path)
+        .with_context(|| format!("Unable to open configuration file {conf_path:?}"))?;
+    read_conf(file, supported_file)
+        .with_context(|| format!("Unable to load configuration file {conf_path:?}"))
+}
+
+/// Read and deserialize a configuration in the given format, without
+/// validating it
+fn read_conf<C, R>(mut reader: R, supported_file: SupportedFile) -> Result<C, ConfigUtilsError>
+where
+    C: DeserializeOwned,
+    R: Read,
+{
+    let mut content = String::new();
+    reader
+        .read_to_string(&mut content)
+        .context("Unable to read configuration")?;
+    trace!("Configuration contents: {content}");
+    supported_file.deserialize(&content)
+}
+
+/// Load a configuration file in the given format, or create it with the
+/// default configuration if it does not exist
+fn load_file<C>(conf_path: &str, supported_file: SupportedFile) -> Result<C, ConfigUtilsError>
+where
+    C: ConfigUtils + Serialize + DeserializeOwned + Debug,
+{
+    // Deserialize the configuration from the file, or create a default
+    // configuration if none exists
+    let conf_path_buf = PathBuf::from(conf_path);
+    let conf = if conf_path_buf.exists() {
+        // Configuration file exists, read and deserialize it
+        read_file(conf_path, supported_file)?
+    } else {
+        // Configuration file doesn't exist, create it with default values and serialize
+        // it
+        if let Some(parent) = conf_path_buf.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Unable to create directory for configuration file {parent:?}")
+            })?;
+        }
+
+        let default_conf = C::default();
+        default_conf.save(conf_path, supported_file)?;
+        default_conf
+    };
+
+    Ok(conf)
+}
+
+/// Find the value at `path` in a configuration value, creating the missing
+/// fields; `None` if a field name on the path is empty, or if a field on the
+/// path is neither an object nor null.
+///
+/// Field names are matched case-insensitively against the existing fields. A
+/// missing field is created in lowercase, as the struct field names are not
+/// known here.
+fn value_at_path<'a>(
+    mut target: &'a mut serde_json::Value,
+    path: &str,
+) -> Option<&'a mut serde_json::Value> {
+    if path.split("__").any(str::is_empty) {
+        return None;
+    }
+    for field in path.split("__").map(str::to_lowercase) {
+        if target.is_null() {
+            *target = serde_json::Value::Object(serde_json::Map::new());
+        }
+        let object = target.as_object_mut()?;
+        let key = object
+            .keys()
+            .find(|key| key.to_lowercase() == field)
+            .cloned()
+            .unwrap_or(field);
+        target = object.entry(key).or_insert(serde_json::Value::Null);
+    }
+    Some(target)
+}
+
+/// Overlay the environment variables named `<prefix>__<FIELD>[__<FIELD>...]`
+/// on a configuration value, e.g. `APP__HTTP__SERVER_URL` on `http.server_url`.
+///
+/// An environment value replacing a string stays a string, otherwise it is
+/// parsed as JSON (numbers, booleans, arrays...) with a fallback to a string.
+/// A value replacing a null or missing field, whose type is unknown, is kept
+/// as a string when `null_as_string` is set.
+///
+/// The variables whose name or value is not valid Unicode are skipped.
+pub(crate) fn overlay_env(conf: &mut serde_json::Value, prefix: &str, null_as_string: bool) {
+    let prefix = format!("{prefix}__");
+    for (name, value) in env::vars_os() {
+        let (Ok(name), Ok(value)) = (name.into_string(), value.into_string()) else {
+            continue;
+        };
+        let Some(target) = name
+            .strip_prefix(&prefix)
+            .and_then(|path| value_at_path(conf, path))
+        else {
+            continue;
+        };
+        trace!("Overriding configuration with environment variable {name}");
+        *target = if target.is_string() || (null_as_string && target.is_null()) {
+            serde_json::Value::String(value)
+        } else {
+            serde_json::from_str(&value).unwrap_or(serde_json::Value::String(value))
+        };
+    }
+}
+
+/// Merge `overlay` into `base`: objects are merged field by field, any other
+/// value of `overlay` replaces the one of `base`.
+fn deep_merge(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                deep_merge(base.entry(key).or_insert(serde_json::Value::Null), value);
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
 }
 
 pub trait ConfigUtils: Default {
+    /// Check the configuration once it is loaded, e.g. that a field is not
+    /// empty or that options set together are compatible.
+    ///
+    /// Called by all the loading functions, which return its error. Accepts
+    /// any configuration by default.
+    fn validate(&self) -> Result<(), ConfigUtilsError> {
+        Ok(())
+    }
+
     fn to_toml(&self, conf_path: &str) -> Result<(), ConfigUtilsError>
     where
         Self: serde::ser::Serialize + std::fmt::Debug,
@@ -139,19 +436,133 @@ pub trait ConfigUtils: Default {
         Self: serde::ser::Serialize + std::fmt::Debug,
     {
         trace!("Saving configuration to {conf_path:?}");
-        let content = match supported_file {
-            SupportedFile::Json => serde_json::to_string_pretty(&self)
-                .with_context(|| format!("Unable to serialize default configuration {self:?}"))?,
-            SupportedFile::Toml => toml::to_string_pretty(&self)
-                .with_context(|| format!("Unable to serialize default configuration {self:?}"))?,
-        };
-        fs::write(conf_path, &content).with_context(|| {
+        let content = supported_file.serialize(self)?;
+        write_atomically(Path::new(conf_path), content.as_bytes(), false).with_context(|| {
             format!("Unable to write default configuration to file {conf_path:?}\n{self:?}")
         })?;
 
         Ok(())
     }
 
+    /// Save the configuration like `save`, in a file only readable and
+    /// writable by its owner (mode `600`) on Unix, for configurations holding
+    /// secrets such as tokens or passwords.
+    ///
+    /// On Windows the file keeps the permissions inherited from its
+    /// directory, which is private to the user in the user profile.
+    fn save_secure(
+        &self,
+        conf_path: &str,
+        supported_file: SupportedFile,
+    ) -> Result<(), ConfigUtilsError>
+    where
+        Self: serde::ser::Serialize + std::fmt::Debug,
+    {
+        trace!("Saving configuration to {conf_path:?} with owner only permissions");
+        let content = supported_file.serialize(self)?;
+        write_atomically(Path::new(conf_path), content.as_bytes(), true)
+            .with_context(|| format!("Unable to write configuration to file {conf_path:?}"))?;
+
+        Ok(())
+    }
+
+    /// Save the configuration encrypted with AES-256-GCM under `key`, in a
+    /// file only readable and writable by its owner like `save_secure`.
+    ///
+    /// The file holds a version byte, the nonce, then the encrypted
+    /// configuration in the given format.
+    #[cfg(feature = "encryption")]
+    fn save_encrypted(
+        &self,
+        conf_path: &str,
+        supported_file: SupportedFile,
+        key: &[u8; 32],
+    ) -> Result<(), ConfigUtilsError>
+    where
+        Self: serde::ser::Serialize + std::fmt::Debug,
+    {
+        trace!("Saving encrypted configuration to {conf_path:?}");
+        let content = supported_file.serialize(self)?;
+        write_atomically(
+            Path::new(conf_path),
+            &encrypt(content.as_bytes(), key)?,
+            true,
+        )
+        .with_context(|| format!("Unable to write configuration to file {conf_path:?}"))?;
+
+        Ok(())
+    }
+
+    /// Load a configuration saved by `save_encrypted`.
+    ///
+    /// A wrong key or a modified file is a `ConfigUtilsError::Conversion`
+    /// error. The file is never created.
+    #[cfg(feature = "encryption")]
+    fn load_encrypted(
+        conf_path: &str,
+        supported_file: SupportedFile,
+        key: &[u8; 32],
+    ) -> Result<Self, ConfigUtilsError>
+    where
+        Self: Sized,
+        Self: DeserializeOwned,
+    {
+        if !Path::new(conf_path).exists() {
+            return Err(ConfigUtilsError::NotFound(format!(
+                "Configuration file {conf_path:?} does not exist"
+            )));
+        }
+        let encrypted = fs::read(conf_path)
+            .with_context(|| format!("Unable to read configuration file {conf_path:?}"))?;
+        // not through `from_reader`, which traces the contents: they are
+        // encrypted to keep them secret
+        let content = String::from_utf8(decrypt(&encrypted, key)?).map_err(|e| {
+            ConfigUtilsError::Conversion(format!("Decrypted configuration is not UTF-8: {e}"))
+        })?;
+        let conf: Self = supported_file.deserialize(&content)?;
+        conf.validate()?;
+        Ok(conf)
+    }
+
+    /// Deserialize the configuration from any reader, e.g. the standard
+    /// input, and validate it
+    fn from_reader<R: Read>(
+        reader: R,
+        supported_file: SupportedFile,
+    ) -> Result<Self, ConfigUtilsError>
+    where
+        Self: Sized,
+        Self: DeserializeOwned,
+    {
+        let conf: Self = read_conf(reader, supported_file)?;
+        conf.validate()?;
+        Ok(conf)
+    }
+
+    /// Serialize the configuration to any writer, e.g. the standard output
+    fn to_writer<W: Write>(
+        &self,
+        mut writer: W,
+        supported_file: SupportedFile,
+    ) -> Result<(), ConfigUtilsError>
+    where
+        Self: serde::ser::Serialize + std::fmt::Debug,
+    {
+        let content = supported_file.serialize(self)?;
+        writer
+            .write_all(content.as_bytes())
+            .context("Unable to write configuration")
+    }
+
+    /// Save the configuration in the format given by the extension of
+    /// `conf_path`: `.toml`, `.json`, `.yaml` or `.yml`
+    fn save_to_path(&self, conf_path: &str) -> Result<(), ConfigUtilsError>
+    where
+        Self: serde::ser::Serialize + std::fmt::Debug,
+    {
+        self.save(conf_path, SupportedFile::from_extension(conf_path)?)
+    }
+
     fn load(conf_path: &str, json: bool) -> Result<Self, ConfigUtilsError>
     where
         Self: Sized,
@@ -159,44 +570,150 @@ pub trait ConfigUtils: Default {
         Self: DeserializeOwned,
         Self: Debug,
     {
-        // Deserialize the configuration from the file, or create a default
-        // configuration if none exists
-        let conf_path_buf = PathBuf::from(conf_path);
-        let conf = if conf_path_buf.exists() {
-            // Configuration file exists, read and deserialize it
-            let content = fs::read_to_string(conf_path)
-                .with_context(|| format!("Unable to read configuration file {conf_path:?}"))?;
-            trace!("Configuration file contents: {content}");
-            if json {
-                serde_json::from_str(&content).with_context(|| {
-                    format!("Error while parsing configuration file {conf_path:?}")
-                })?
-            } else {
-                toml::from_str(&content).with_context(|| {
-                    format!("Error while parsing configuration file {conf_path:?}")
-                })?
-            }
+        let supported_file = if json {
+            SupportedFile::Json
         } else {
-            // Configuration file doesn't exist, create it with default values and serialize
-            // it
-            if let Some(parent) = conf_path_buf.parent() {
-                fs::create_dir_all(parent).with_context(|| {
-                    format!("Unable to create directory for configuration file {parent:?}")
-                })?;
-            }
+            SupportedFile::Toml
+        };
+        let conf: Self = load_file(conf_path, supported_file)?;
+        conf.validate()?;
+        Ok(conf)
+    }
+
+    /// Load the configuration like `load`, but return a
+    /// `ConfigUtilsError::NotFound` error instead of creating the file when
+    /// it does not exist, e.g. in a read-only container.
+    fn load_no_create(
+        conf_path: &str,
+        supported_file: SupportedFile,
+    ) -> Result<Self, ConfigUtilsError>
+    where
+        Self: Sized,
+        Self: DeserializeOwned,
+    {
+        if !Path::new(conf_path).exists() {
+            return Err(ConfigUtilsError::NotFound(format!(
+                "Configuration file {conf_path:?} does not exist"
+            )));
+        }
+        let conf: Self = read_file(conf_path, supported_file)?;
+        conf.validate()?;
+        Ok(conf)
+    }
 
-            let default_conf = Self::default();
-            if json {
-                default_conf.to_json(conf_path)?;
-            } else {
-                default_conf.to_toml(conf_path)?;
+    /// Load the configuration in the format given by the extension of
+    /// `conf_path` (`.toml`, `.json`, `.yaml` or `.yml`), or create it with
+    /// the default configuration if it does not exist
+    fn from_path(conf_path: &str) -> Result<Self, ConfigUtilsError>
+    where
+        Self: Sized,
+        Self: Serialize,
+        Self: DeserializeOwned,
+        Self: Debug,
+    {
+        let conf: Self = load_file(conf_path, SupportedFile::from_extension(conf_path)?)?;
+        conf.validate()?;
+        Ok(conf)
+    }
+
+    /// Load the configuration like `from_path`, then override its fields
+    /// with the environment variables named `<prefix>__<FIELD>`, using `__`
+    /// to reach nested fields, e.g. `APP__SERVER_URL` or
+    /// `APP__OAUTH2_CONF__CLIENT_ID`.
+    ///
+    /// The environment variables take precedence over the file. A value
+    /// keeps the type of the field it replaces; for a null or missing field,
+    /// e.g. an `Option` set to `None`, it is parsed as JSON and used as a
+    /// string if the configuration cannot be deserialized that way, so
+    /// `APP__TOKEN=1234` sets an `Option<String>` to `"1234"`.
+    ///
+    /// Variable names are matched case-insensitively against the fields of
+    /// the file. A field missing from the file is matched in lowercase, so a
+    /// field renamed with uppercase letters, e.g. `serverURL`, can only be
+    /// overridden if it is present in the file.
+    fn load_with_env(conf_path: &str, prefix: &str) -> Result<Self, ConfigUtilsError>
+    where
+        Self: Sized,
+        Self: Serialize,
+        Self: DeserializeOwned,
+        Self: Debug,
+    {
+        // the file alone may not be valid, only the result is validated
+        let conf: Self = load_file(conf_path, SupportedFile::from_extension(conf_path)?)?;
+        let mut value = serde_json::to_value(&conf)
+            .with_context(|| format!("Unable to serialize configuration {conf:?}"))?;
+        let mut typed_value = value.clone();
+        overlay_env(&mut typed_value, prefix, false);
+        let conf: Self = match serde_json::from_value(typed_value) {
+            Ok(conf) => conf,
+            Err(_) => {
+                // a value such as `1234` or `true` may be meant for a string
+                overlay_env(&mut value, prefix, true);
+                serde_json::from_value(value).with_context(|| {
+                    format!(
+                        "Error while applying the {prefix}__* environment variables to \
+                         {conf_path:?}"
+                    )
+                })?
             }
-            default_conf
         };
+        conf.validate()?;
+        Ok(conf)
+    }
 
+    /// Load the configuration from several files, each one overriding the
+    /// fields set by the previous ones, on top of the default configuration.
+    ///
+    /// The format of each file is given by its extension. Missing files are
+    /// skipped, so environment or host specific overlays may be optional.
+    fn load_layered(paths: &[&str]) -> Result<Self, ConfigUtilsError>
+    where
+        Self: Sized,
+        Self: Serialize,
+        Self: DeserializeOwned,
+        Self: Debug,
+    {
+        let default_conf = Self::default();
+        let mut conf = serde_json::to_value(&default_conf).with_context(|| {
+            format!("Unable to serialize default configuration {default_conf:?}")
+        })?;
+        for conf_path in paths {
+            if !Path::new(conf_path).exists() {
+                trace!("Skipping missing configuration file {conf_path:?}");
+                continue;
+            }
+            let content = fs::read_to_string(conf_path)
+                .with_context(|| format!("Unable to read configuration file {conf_path:?}"))?;
+            let layer = SupportedFile::from_extension(conf_path)?
+                .deserialize(&content)
+                .with_context(|| format!("Unable to load configuration file {conf_path:?}"))?;
+            deep_merge(&mut conf, layer);
+        }
+        let conf: Self = serde_json::from_value(conf)
+            .with_context(|| format!("Error while merging configuration files {paths:?}"))?;
+        conf.validate()?;
         Ok(conf)
     }
 
+    /// Watch the configuration file and call `on_change` with the reloaded
+    /// configuration, or the error while reloading it, each time it changes.
+    ///
+    /// Changes in quick succession, such as an editor truncating then
+    /// writing the file, are reloaded once when they end. A removed file is
+    /// not reloaded, nor recreated. The file is watched until the returned
+    /// `ConfigWatcher` is dropped.
+    #[cfg(feature = "watch")]
+    fn watch<F>(conf_path: &str, on_change: F) -> Result<ConfigWatcher, ConfigUtilsError>
+    where
+        Self: Sized,
+        Self: Serialize,
+        Self: DeserializeOwned,
+        Self: Debug,
+        F: FnMut(Result<Self, ConfigUtilsError>) + Send + 'static,
+    {
+        watch_file(conf_path, on_change)
+    }
+
     fn from_toml(conf_path: &str) -> Result<Self, ConfigUtilsError>
     where
         Self: Sized,
@@ -0,0 +1,57 @@
+use aes_gcm::{
+    Aes256Gcm, KeyInit,
+    aead::{Aead, AeadCore, OsRng},
+};
+
+use crate::error::ConfigUtilsError;
+
+/// The version of the format of the encrypted files, their first byte
+const VERSION: u8 = 1;
+
+/// The length of an AES-GCM nonce
+const NONCE_LENGTH: usize = 12;
+
+/// Encrypt a serialized configuration with AES-256-GCM under `key`.
+///
+/// The result is the version byte, the random nonce and the ciphertext
+/// followed by its authentication tag.
+pub(crate) fn encrypt(plaintext: &[u8], key: &[u8; 32]) -> Result<Vec<u8>, ConfigUtilsError> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = Aes256Gcm::new(key.into())
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| ConfigUtilsError::Conversion("Unable to encrypt configuration".to_owned()))?;
+
+    let mut encrypted = Vec::with_capacity(1 + NONCE_LENGTH + ciphertext.len());
+    encrypted.push(VERSION);
+    encrypted.extend_from_slice(&nonce);
+    encrypted.extend_from_slice(&ciphertext);
+    Ok(encrypted)
+}
+
+/// Decrypt a configuration encrypted by `encrypt`.
+///
+/// A wrong key or a modified file fails the authentication of the
+/// ciphertext, so nothing is ever returned but the original configuration.
+pub(crate) fn decrypt(encrypted: &[u8], key: &[u8; 32]) -> Result<Vec<u8>, ConfigUtilsError> {
+    let (version, rest) = encrypted
+        .split_first()
+        .ok_or_else(|| ConfigUtilsError::Conversion("Empty encrypted configuration".to_owned()))?;
+    if *version != VERSION {
+        return Err(ConfigUtilsError::NotSupported(format!(
+            "Unknown encrypted configuration version {version}"
+        )));
+    }
+    if rest.len() < NONCE_LENGTH {
+        return Err(ConfigUtilsError::Conversion(
+            "Truncated encrypted configuration".to_owned(),
+        ));
+    }
+    let (nonce, ciphertext) = rest.split_at(NONCE_LENGTH);
+    Aes256Gcm::new(key.into())
+        .decrypt(nonce.into(), ciphertext)
+        .map_err(|_| {
+            ConfigUtilsError::Conversion(
+                "Unable to decrypt configuration: wrong key or corrupted file".to_owned(),
+            )
+        })
+}
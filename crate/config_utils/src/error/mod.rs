@@ -18,6 +18,9 @@ pub enum ConfigUtilsError {
     #[error("Not found: {0}")]
     NotFound(String),
 
+    #[error("Not supported: {0}")]
+    NotSupported(String),
+
     #[error("Unexpected Error: {0}")]
     UnexpectedError(String),
 
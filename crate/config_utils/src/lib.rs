@@ -1,8 +1,16 @@
-pub use config_utils::{ConfigUtils, get_default_conf_path, get_home_folder, location};
+pub use config_utils::{
+    ConfigUtils, SupportedFile, get_default_conf_path, get_home_folder, get_xdg_conf_path, location,
+};
 pub use error::ConfigUtilsError;
+#[cfg(feature = "watch")]
+pub use watch::ConfigWatcher;
 
 mod config_utils;
+#[cfg(feature = "encryption")]
+mod encryption;
 mod error;
+#[cfg(feature = "watch")]
+mod watch;
 
 #[cfg(test)]
 pub mod tests;
@@ -5,14 +5,20 @@ use super::*;
 use std::env;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
 
 #[cfg(unix)]
 const TEST_FILE: &str = "/bin/cat";
 #[cfg(windows)]
 const TEST_FILE: &str = "C:\\Windows\\System32\\cmd.exe";
 
+/// Serializes the tests changing the `HOME` or `XDG_CONFIG_HOME`
+/// environment variables, which are read by the other ones
+static HOME_ENV: Mutex<()> = Mutex::new(());
+
 #[test]
 fn test_location() {
+    let _guard = HOME_ENV.lock().unwrap_or_else(PoisonError::into_inner);
     // Test with CLI argument
     let conf_path = PathBuf::from(TEST_FILE);
     let result = location(
@@ -35,6 +41,7 @@ fn test_location() {
 
     // Test with default path
     env::remove_var("TEST_CONF");
+    let home = env::var_os("HOME");
     env::set_var("HOME", "/fake/home");
     let result = location(
         None,
@@ -42,6 +49,10 @@ fn test_location() {
         "config/default.toml",
         "/etc/default.toml",
     );
+    match home {
+        Some(home) => env::set_var("HOME", home),
+        None => env::remove_var("HOME"),
+    }
     assert_eq!(
         result.unwrap(),
         PathBuf::from("/fake/home/config/default.toml")
@@ -75,3 +86,486 @@ fn test_config_utils_save_and_load() {
     // Clean up
     fs::remove_file(conf_path).unwrap();
 }
+
+#[test]
+fn test_config_utils_load_no_create() {
+    let conf_dir = "test_config_no_create";
+    let conf_path = "test_config_no_create/conf.toml";
+    let _ = fs::remove_dir_all(conf_dir);
+
+    let result = TestConfig::load_no_create(conf_path, SupportedFile::Toml);
+    assert!(matches!(result, Err(ConfigUtilsError::NotFound(_))));
+    // nothing was created
+    assert!(!PathBuf::from(conf_dir).exists());
+
+    fs::create_dir(conf_dir).unwrap();
+    let config = TestConfig {
+        key: "existing".to_string(),
+    };
+    config.to_toml(conf_path).unwrap();
+    let loaded_config = TestConfig::load_no_create(conf_path, SupportedFile::Toml).unwrap();
+    assert_eq!(config.key, loaded_config.key);
+
+    // Clean up
+    fs::remove_dir_all(conf_dir).unwrap();
+}
+
+#[cfg(feature = "encryption")]
+#[test]
+fn test_config_utils_encrypted() {
+    let conf_path = "test_config_encrypted.toml.enc";
+    let key = [7_u8; 32];
+    let config = TestConfig {
+        key: "secret".to_string(),
+    };
+
+    config
+        .save_encrypted(conf_path, SupportedFile::Toml, &key)
+        .unwrap();
+    let content = fs::read(conf_path).unwrap();
+    assert_eq!(content.first(), Some(&1));
+    assert!(!String::from_utf8_lossy(&content).contains("secret"));
+
+    // the decrypted contents are never logged
+    let logs = cosmian_logger::log_init_capture();
+    let loaded_config = TestConfig::load_encrypted(conf_path, SupportedFile::Toml, &key).unwrap();
+    assert_eq!(config.key, loaded_config.key);
+    assert!(
+        logs.events()
+            .iter()
+            .all(|event| !event.message.contains("secret"))
+    );
+    drop(logs);
+
+    let result = TestConfig::load_encrypted(conf_path, SupportedFile::Toml, &[8_u8; 32]);
+    assert!(matches!(result, Err(ConfigUtilsError::Conversion(_))));
+
+    fs::write(conf_path, [1, 2, 3]).unwrap();
+    let result = TestConfig::load_encrypted(conf_path, SupportedFile::Toml, &key);
+    assert!(matches!(result, Err(ConfigUtilsError::Conversion(_))));
+
+    // Clean up
+    fs::remove_file(conf_path).unwrap();
+}
+
+#[test]
+fn test_config_utils_format_from_extension() {
+    let config = TestConfig {
+        key: "value".to_string(),
+    };
+
+    for conf_path in [
+        "test_config_ext.toml",
+        "test_config_ext.json",
+        #[cfg(feature = "yaml")]
+        "test_config_ext.yaml",
+    ] {
+        config.save_to_path(conf_path).unwrap();
+        let loaded_config = TestConfig::from_path(conf_path).unwrap();
+        assert_eq!(config.key, loaded_config.key);
+        fs::remove_file(conf_path).unwrap();
+    }
+
+    // the YAML file is not mistaken for TOML
+    #[cfg(feature = "yaml")]
+    {
+        config
+            .save("test_config_ext.yml", SupportedFile::Yaml)
+            .unwrap();
+        assert!(TestConfig::from_toml("test_config_ext.yml").is_err());
+        assert_eq!(
+            TestConfig::from_path("test_config_ext.yml").unwrap().key,
+            "value"
+        );
+        fs::remove_file("test_config_ext.yml").unwrap();
+    }
+    #[cfg(not(feature = "yaml"))]
+    assert!(matches!(
+        config.save_to_path("test_config_ext.yml"),
+        Err(ConfigUtilsError::NotSupported(_))
+    ));
+
+    assert!(matches!(
+        TestConfig::from_path("test_config_ext.ini"),
+        Err(ConfigUtilsError::NotSupported(_))
+    ));
+    assert!(matches!(
+        config.save_to_path("test_config_ext"),
+        Err(ConfigUtilsError::NotSupported(_))
+    ));
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct NestedTestConfig {
+    key: String,
+    port: u16,
+    nested: TestConfig,
+}
+
+impl ConfigUtils for NestedTestConfig {}
+
+#[test]
+fn test_config_utils_load_with_env() {
+    let conf_path = "test_config_env.json";
+    let config = NestedTestConfig {
+        key: "file".to_string(),
+        port: 9998,
+        nested: TestConfig {
+            key: "file".to_string(),
+        },
+    };
+    config.save_to_path(conf_path).unwrap();
+
+    env::set_var("TEST_ENV_CONF__KEY", "1234");
+    env::set_var("TEST_ENV_CONF__PORT", "443");
+    env::set_var("TEST_ENV_CONF__NESTED__KEY", "env");
+    let loaded_config = NestedTestConfig::load_with_env(conf_path, "TEST_ENV_CONF").unwrap();
+    assert_eq!(loaded_config.key, "1234");
+    assert_eq!(loaded_config.port, 443);
+    assert_eq!(loaded_config.nested.key, "env");
+
+    env::set_var("TEST_ENV_CONF__PORT", "not a port");
+    assert!(NestedTestConfig::load_with_env(conf_path, "TEST_ENV_CONF").is_err());
+
+    // invalid Unicode in an unrelated variable is skipped
+    #[cfg(unix)]
+    env::set_var(
+        "TEST_ENV_CONF_INVALID_UNICODE",
+        <std::ffi::OsStr as std::os::unix::ffi::OsStrExt>::from_bytes(b"\xff"),
+    );
+    env::set_var("TEST_ENV_CONF__PORT", "443");
+    assert!(NestedTestConfig::load_with_env(conf_path, "TEST_ENV_CONF").is_ok());
+    #[cfg(unix)]
+    env::remove_var("TEST_ENV_CONF_INVALID_UNICODE");
+
+    env::remove_var("TEST_ENV_CONF__KEY");
+    env::remove_var("TEST_ENV_CONF__PORT");
+    env::remove_var("TEST_ENV_CONF__NESTED__KEY");
+    fs::remove_file(conf_path).unwrap();
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct OptionalTestConfig {
+    token: Option<String>,
+    enabled: Option<bool>,
+    port: Option<u16>,
+}
+
+impl ConfigUtils for OptionalTestConfig {}
+
+#[test]
+fn test_config_utils_load_with_env_none() {
+    let conf_path = "test_config_env_none.json";
+    OptionalTestConfig::default()
+        .save_to_path(conf_path)
+        .unwrap();
+
+    // a string field set to `None` keeps a numeric looking value as a string
+    env::set_var("TEST_ENV_NONE_CONF__TOKEN", "1234");
+    let loaded_config = OptionalTestConfig::load_with_env(conf_path, "TEST_ENV_NONE_CONF").unwrap();
+    assert_eq!(loaded_config.token.as_deref(), Some("1234"));
+    assert_eq!(loaded_config.port, None);
+
+    // while the other fields set to `None` are still typed
+    env::set_var("TEST_ENV_NONE_CONF__ENABLED", "true");
+    env::set_var("TEST_ENV_NONE_CONF__PORT", "443");
+    env::set_var("TEST_ENV_NONE_CONF__TOKEN", "abcd");
+    let loaded_config = OptionalTestConfig::load_with_env(conf_path, "TEST_ENV_NONE_CONF").unwrap();
+    assert_eq!(loaded_config.token.as_deref(), Some("abcd"));
+    assert_eq!(loaded_config.enabled, Some(true));
+    assert_eq!(loaded_config.port, Some(443));
+
+    env::remove_var("TEST_ENV_NONE_CONF__TOKEN");
+    env::remove_var("TEST_ENV_NONE_CONF__ENABLED");
+    env::remove_var("TEST_ENV_NONE_CONF__PORT");
+    fs::remove_file(conf_path).unwrap();
+}
+
+#[test]
+fn test_config_utils_env_empty_field() {
+    env::set_var("TEST_EMPTY_FIELD__", "root");
+    env::set_var("TEST_EMPTY_FIELD__KEY____NESTED", "nested");
+    let mut conf = serde_json::json!({"key": "file"});
+    config_utils::overlay_env(&mut conf, "TEST_EMPTY_FIELD", false);
+    assert_eq!(conf, serde_json::json!({"key": "file"}));
+    env::remove_var("TEST_EMPTY_FIELD__");
+    env::remove_var("TEST_EMPTY_FIELD__KEY____NESTED");
+}
+
+#[test]
+fn test_config_utils_save_replaces_file() {
+    let conf_path = "test_config_atomic.toml";
+    fs::write(conf_path, "a previous and much longer content").unwrap();
+
+    let config = TestConfig {
+        key: "value".to_string(),
+    };
+    config.to_toml(conf_path).unwrap();
+    assert_eq!(TestConfig::from_toml(conf_path).unwrap().key, "value");
+
+    // no temporary file is left behind
+    let leftovers = fs::read_dir(".")
+        .unwrap()
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with(".test_config_atomic.toml")
+        })
+        .count();
+    assert_eq!(leftovers, 0);
+
+    fs::remove_file(conf_path).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_config_utils_save_keeps_permissions_and_links() {
+    use std::os::unix::fs::{PermissionsExt, symlink};
+
+    let conf_path = "test_config_keep.toml";
+    let link_path = "test_config_keep_link.toml";
+    let _ = fs::remove_file(link_path);
+    let config = TestConfig {
+        key: "value".to_string(),
+    };
+
+    config.to_toml(conf_path).unwrap();
+    fs::set_permissions(conf_path, fs::Permissions::from_mode(0o600)).unwrap();
+    config.to_toml(conf_path).unwrap();
+    let mode = fs::metadata(conf_path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    // the link is kept and the file it points to is replaced
+    symlink(conf_path, link_path).unwrap();
+    let config = TestConfig {
+        key: "through the link".to_string(),
+    };
+    config.to_toml(link_path).unwrap();
+    assert!(fs::symlink_metadata(link_path).unwrap().is_symlink());
+    assert_eq!(
+        TestConfig::from_toml(conf_path).unwrap().key,
+        "through the link"
+    );
+
+    fs::remove_file(link_path).unwrap();
+    fs::remove_file(conf_path).unwrap();
+}
+
+#[test]
+fn test_config_utils_concurrent_saves() {
+    let conf_path = "test_config_concurrent.toml";
+
+    let threads = (0..8)
+        .map(|i| {
+            std::thread::spawn(move || {
+                let config = TestConfig {
+                    key: format!("thread {i}"),
+                };
+                for _ in 0..20 {
+                    config.to_toml(conf_path).unwrap();
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert!(
+        TestConfig::from_toml(conf_path)
+            .unwrap()
+            .key
+            .starts_with("thread ")
+    );
+
+    fs::remove_file(conf_path).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_config_utils_save_secure() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let conf_path = "test_config_secure.toml";
+    let config = TestConfig {
+        key: "secret".to_string(),
+    };
+
+    config.save_secure(conf_path, SupportedFile::Toml).unwrap();
+    let mode = fs::metadata(conf_path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    assert_eq!(TestConfig::from_toml(conf_path).unwrap().key, "secret");
+
+    // an existing world readable file is replaced
+    fs::set_permissions(conf_path, fs::Permissions::from_mode(0o644)).unwrap();
+    config.save_secure(conf_path, SupportedFile::Toml).unwrap();
+    let mode = fs::metadata(conf_path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    fs::remove_file(conf_path).unwrap();
+}
+
+#[test]
+fn test_config_utils_load_layered() {
+    let base_path = "test_config_layer_base.toml";
+    let overlay_path = "test_config_layer_overlay.json";
+    fs::write(
+        base_path,
+        "key = \"base\"\nport = 9998\n[nested]\nkey = \"base\"\n",
+    )
+    .unwrap();
+    fs::write(
+        overlay_path,
+        r#"{"port": 443, "nested": {"key": "overlay"}}"#,
+    )
+    .unwrap();
+
+    let config = NestedTestConfig::load_layered(&[
+        base_path,
+        "test_config_layer_missing.toml",
+        overlay_path,
+    ])
+    .unwrap();
+    assert_eq!(config.key, "base");
+    assert_eq!(config.port, 443);
+    assert_eq!(config.nested.key, "overlay");
+
+    // nothing to load: the default configuration
+    let config = NestedTestConfig::load_layered(&["test_config_layer_missing.toml"]).unwrap();
+    assert_eq!(config.port, 0);
+
+    fs::remove_file(base_path).unwrap();
+    fs::remove_file(overlay_path).unwrap();
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct ValidatedTestConfig {
+    server_url: String,
+}
+
+impl ConfigUtils for ValidatedTestConfig {
+    fn validate(&self) -> Result<(), ConfigUtilsError> {
+        if self.server_url.is_empty() {
+            return Err(ConfigUtilsError::Default("server_url is empty".to_owned()));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_config_utils_validate() {
+    let conf_path = "test_config_validate.json";
+
+    ValidatedTestConfig::default().to_json(conf_path).unwrap();
+    assert!(ValidatedTestConfig::from_json(conf_path).is_err());
+    assert!(ValidatedTestConfig::from_path(conf_path).is_err());
+    assert!(ValidatedTestConfig::load_layered(&[conf_path]).is_err());
+    assert!(
+        ValidatedTestConfig::from_reader(&br#"{"server_url": ""}"#[..], SupportedFile::Json)
+            .is_err()
+    );
+    let config = ValidatedTestConfig::from_reader(
+        &br#"{"server_url": "https://localhost"}"#[..],
+        SupportedFile::Json,
+    )
+    .unwrap();
+    assert_eq!(config.server_url, "https://localhost");
+
+    // the environment may complete an invalid file
+    env::set_var("TEST_VALIDATE_CONF__SERVER_URL", "https://localhost");
+    let config = ValidatedTestConfig::load_with_env(conf_path, "TEST_VALIDATE_CONF").unwrap();
+    assert_eq!(config.server_url, "https://localhost");
+    env::remove_var("TEST_VALIDATE_CONF__SERVER_URL");
+
+    fs::remove_file(conf_path).unwrap();
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+#[test]
+fn test_xdg_conf_path() {
+    let _guard = HOME_ENV.lock().unwrap_or_else(PoisonError::into_inner);
+    let home = get_home_folder().unwrap();
+
+    env::set_var("XDG_CONFIG_HOME", "/tmp/xdg");
+    assert_eq!(
+        get_xdg_conf_path("cosmian/cosmian.toml").unwrap(),
+        PathBuf::from("/tmp/xdg/cosmian/cosmian.toml")
+    );
+
+    // relative values are ignored, as the specification requires
+    env::set_var("XDG_CONFIG_HOME", "xdg");
+    assert_eq!(
+        get_xdg_conf_path("cosmian/cosmian.toml").unwrap(),
+        home.join(".config/cosmian/cosmian.toml")
+    );
+
+    env::remove_var("XDG_CONFIG_HOME");
+    assert_eq!(
+        get_xdg_conf_path("cosmian/cosmian.toml").unwrap(),
+        home.join(".config/cosmian/cosmian.toml")
+    );
+}
+
+#[test]
+fn test_config_utils_reader_writer() {
+    let config = TestConfig {
+        key: "from_reader".to_string(),
+    };
+
+    for format in [
+        SupportedFile::Toml,
+        SupportedFile::Json,
+        #[cfg(feature = "yaml")]
+        SupportedFile::Yaml,
+    ] {
+        let mut content = Vec::new();
+        config.to_writer(&mut content, format).unwrap();
+        let read = TestConfig::from_reader(content.as_slice(), format).unwrap();
+        assert_eq!(read.key, config.key, "Failed for {format:?}");
+    }
+
+    let read = TestConfig::from_reader(&b"key = \"stdin\""[..], SupportedFile::Toml).unwrap();
+    assert_eq!(read.key, "stdin");
+    assert!(TestConfig::from_reader(&b"key = "[..], SupportedFile::Toml).is_err());
+}
+
+#[cfg(feature = "watch")]
+#[test]
+fn test_config_utils_watch() {
+    use std::{sync::mpsc, time::Duration};
+
+    let conf_path = "test_config_watch.toml";
+    TestConfig {
+        key: "initial".to_string(),
+    }
+    .to_toml(conf_path)
+    .unwrap();
+
+    let (sender, receiver) = mpsc::channel();
+    let watcher = TestConfig::watch(conf_path, move |config| {
+        let _ = sender.send(config.map(|config| config.key));
+    })
+    .unwrap();
+
+    TestConfig {
+        key: "updated".to_string(),
+    }
+    .to_toml(conf_path)
+    .unwrap();
+    let key = receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(key.unwrap(), "updated");
+
+    fs::write(conf_path, "key = ").unwrap();
+    let key = receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert!(key.is_err());
+
+    // a removed file is not recreated with the default configuration
+    fs::remove_file(conf_path).unwrap();
+    if let Ok(key) = receiver.recv_timeout(Duration::from_secs(1)) {
+        assert!(key.is_err());
+    }
+    assert!(!std::path::Path::new(conf_path).exists());
+
+    drop(watcher);
+}
@@ -0,0 +1,95 @@
+use std::{
+    fmt::Debug,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Serialize, de::DeserializeOwned};
+use tracing::{debug, trace};
+
+use crate::{
+    ConfigUtils, SupportedFile,
+    config_utils::read_file,
+    error::{ConfigUtilsError, result::ConfigUtilsResultHelper},
+};
+
+/// How long the file must stay untouched after a change before it is
+/// reloaded, so that a save made of several writes is only parsed once done
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Watches a configuration file until dropped.
+pub struct ConfigWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+/// Watch `conf_path` and call `on_change` with the reloaded configuration
+/// each time it changes.
+///
+/// The directory of the file is watched rather than the file itself, so that
+/// files replaced by a rename, as `ConfigUtils::save` does, are still seen.
+pub(crate) fn watch_file<C, F>(
+    conf_path: &str,
+    mut on_change: F,
+) -> Result<ConfigWatcher, ConfigUtilsError>
+where
+    C: ConfigUtils + Serialize + DeserializeOwned + Debug,
+    F: FnMut(Result<C, ConfigUtilsError>) + Send + 'static,
+{
+    let supported_file = SupportedFile::from_extension(conf_path)?;
+    let path = PathBuf::from(conf_path);
+    let file_name = path
+        .file_name()
+        .map(ToOwned::to_owned)
+        .ok_or_else(|| ConfigUtilsError::NotFound(format!("{conf_path:?} is not a file path")))?;
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        if let Ok(event) = event {
+            if event
+                .paths
+                .iter()
+                .any(|path| path.file_name() == Some(&file_name))
+            {
+                let _ = sender.send(());
+            }
+        }
+    })
+    .context("Unable to create the configuration file watcher")?;
+    watcher
+        .watch(&directory, RecursiveMode::NonRecursive)
+        .with_context(|| format!("Unable to watch the configuration directory {directory:?}"))?;
+
+    let conf_path = conf_path.to_owned();
+    thread::spawn(move || {
+        // the thread ends when the watcher, and so the sender, is dropped
+        while receiver.recv().is_ok() {
+            loop {
+                match receiver.recv_timeout(DEBOUNCE) {
+                    Ok(()) => trace!("Configuration file {conf_path:?} is still changing"),
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+            if !Path::new(&conf_path).exists() {
+                debug!("Configuration file {conf_path:?} was removed, not reloading it");
+                continue;
+            }
+            debug!("Reloading configuration file {conf_path:?}");
+            // never recreated with the defaults: removed after the check
+            // above, the file fails to open and the error is reported
+            on_change(
+                read_file::<C>(&conf_path, supported_file)
+                    .and_then(|conf| conf.validate().map(|()| conf)),
+            );
+        }
+    });
+
+    Ok(ConfigWatcher { _watcher: watcher })
+}
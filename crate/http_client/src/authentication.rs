@@ -4,6 +4,13 @@
 //! extractors.
 //! The available authenticators are:
 //! - `Session`: A session-based authenticator that uses a cookie to store an identifier.
+//! - `JwtAuthenticator`: A JWT authenticator that verifies the `Authorization: Bearer` token.
+//! - `ApiKeyAuthenticator`: An authenticator that checks a static API key sent in a header.
+//! - `ClientCertAuthenticator`: An authenticator that identifies the client by its TLS
+//!   certificate.
+//!
+//! Authenticators are combined with `Either`, [`Either3`] and [`Either4`], where one of them
+//! must succeed, or with [`All`], where all of them must succeed.
 //!
 //! # Examples
 //! ```rust,no_run
@@ -29,7 +36,12 @@
 //! # }
 //! ```
 
+pub mod all;
+pub mod api_key;
+pub mod client_cert;
 pub mod either;
+#[cfg(feature = "jwt")]
+pub mod jwt;
 #[cfg(feature = "session")]
 pub mod session;
 
@@ -38,7 +50,8 @@ use std::future::{Ready, ready};
 use actix_web::{FromRequest, HttpRequest, dev::Payload};
 use derive_more::{Deref, DerefMut};
 
-pub use either::EitherExt;
+pub use all::All;
+pub use either::{Either3, Either4, EitherExt};
 
 /// The `Authenticate` trait is used to authenticate a request.
 ///
@@ -0,0 +1,163 @@
+//! Extractor requiring several authenticators.
+//!
+//! Where `Either` and [`Either3`](super::Either3) accept a request as soon as one authenticator
+//! succeeds, [`All`] accepts it only if every authenticator succeeds, e.g. a valid session and a
+//! valid CSRF token header.
+//!
+//! # Example
+//! ```rust,no_run
+//! # #[cfg(feature = "session")]
+//! # mod doc {
+//! use actix_web::{Error, HttpRequest, error, post};
+//! use cosmian_http_client::authentication::{All, Authenticate, session::Session};
+//!
+//! struct CsrfToken(String);
+//!
+//! impl Authenticate for CsrfToken {
+//!    type Output = String;
+//!    type Error = Error;
+//!
+//!    fn authenticate(request: &HttpRequest) -> Result<Self, Self::Error> {
+//!         request
+//!             .headers()
+//!             .get("X-CSRF-Token")
+//!             .and_then(|value| value.to_str().ok())
+//!             .map(|value| Self(value.to_owned()))
+//!             .ok_or_else(|| error::ErrorForbidden("missing CSRF token"))
+//!    }
+//!
+//!    fn data(&self) -> &Self::Output {
+//!         &self.0
+//!    }
+//! }
+//!
+//! #[post("/transfer")]
+//! async fn transfer(authentication: All<Session<String>, CsrfToken>) -> String {
+//!    let (user, _token) = authentication.data();
+//!    format!("Transfer made by {user}")
+//! }
+//! # }
+//! ```
+
+use std::future::{Ready, ready};
+
+use actix_web::{FromRequest, HttpRequest, dev::Payload};
+
+use super::{Authenticate, Authenticated};
+
+/// An extractor for a request authenticated by both authenticators.
+///
+/// `A` is tried first: if it fails, its error is returned and `B` is not tried.
+pub struct All<A: Authenticate, B: Authenticate>(pub Authenticated<A>, pub Authenticated<B>);
+
+impl<A: Authenticate, B: Authenticate> All<A, B> {
+    /// Returns references to the data of both authenticators.
+    pub fn data(&self) -> (&A::Output, &B::Output) {
+        (self.0.data(), self.1.data())
+    }
+
+    /// Unwrap into both authenticators.
+    pub fn into_inner(self) -> (A, B) {
+        (self.0.into_inner(), self.1.into_inner())
+    }
+}
+
+impl<A, B> FromRequest for All<A, B>
+where
+    A: Authenticate,
+    A::Error: Into<actix_web::Error>,
+    B: Authenticate,
+    B::Error: Into<actix_web::Error>,
+{
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let result = A::authenticate(req).map_err(Into::into).and_then(|a| {
+            B::authenticate(req)
+                .map(|b| Self(Authenticated(a), Authenticated(b)))
+                .map_err(Into::into)
+        });
+        ready(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        App, Error, HttpRequest, HttpResponse, Responder, error, get, http::StatusCode, test,
+    };
+
+    use super::All;
+    use crate::authentication::Authenticate;
+
+    struct Session(String);
+
+    impl Authenticate for Session {
+        type Output = String;
+        type Error = Error;
+
+        fn authenticate(request: &HttpRequest) -> Result<Self, Self::Error> {
+            request
+                .headers()
+                .get("Session")
+                .and_then(|value| value.to_str().ok())
+                .map(|value| Self(value.to_owned()))
+                .ok_or_else(|| error::ErrorUnauthorized("unauthorized"))
+        }
+
+        fn data(&self) -> &Self::Output {
+            &self.0
+        }
+    }
+
+    struct Csrf(String);
+
+    impl Authenticate for Csrf {
+        type Output = String;
+        type Error = Error;
+
+        fn authenticate(request: &HttpRequest) -> Result<Self, Self::Error> {
+            request
+                .headers()
+                .get("Csrf")
+                .and_then(|value| value.to_str().ok())
+                .map(|value| Self(value.to_owned()))
+                .ok_or_else(|| error::ErrorForbidden("forbidden"))
+        }
+
+        fn data(&self) -> &Self::Output {
+            &self.0
+        }
+    }
+
+    #[get("/")]
+    async fn get_data(authentication: All<Session, Csrf>) -> impl Responder {
+        let (session, csrf) = authentication.data();
+        HttpResponse::Ok().body(format!("{session}:{csrf}"))
+    }
+
+    #[actix_web::test]
+    async fn all_authentication() {
+        let app = test::init_service(App::new().service(get_data)).await;
+
+        let tests: [(&[(&str, &str)], StatusCode); 4] = [
+            (&[("Session", "user"), ("Csrf", "token")], StatusCode::OK),
+            (&[("Csrf", "token")], StatusCode::UNAUTHORIZED),
+            (&[("Session", "user")], StatusCode::FORBIDDEN),
+            // the first authenticator to fail gives the status
+            (&[], StatusCode::UNAUTHORIZED),
+        ];
+        for (headers, status_code) in tests {
+            let mut request = test::TestRequest::get().uri("/");
+            for header in headers {
+                request = request.insert_header(*header);
+            }
+            let result = test::call_service(&app, request.to_request()).await;
+            assert_eq!(result.status(), status_code, "Failed for {headers:?}");
+            if status_code == StatusCode::OK {
+                assert_eq!(test::read_body(result).await, "user:token");
+            }
+        }
+    }
+}
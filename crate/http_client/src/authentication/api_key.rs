@@ -0,0 +1,216 @@
+//! API key authenticator.
+//!
+//! This authenticator checks a static API key sent in a request header, `X-API-Key` by default.
+//! The valid keys are an [`ApiKeyConfig`] that must be registered beforehand as application data:
+//!
+//! ```rust,no_run
+//! use actix_web::{App, get};
+//! use cosmian_http_client::authentication::{
+//!     Authenticate, Authenticated,
+//!     api_key::{ApiKeyAuthenticator, ApiKeyConfig},
+//! };
+//!
+//! #[get("/")]
+//! async fn hello(api_key: Authenticated<ApiKeyAuthenticator>) -> String {
+//!     format!("Hello, {}!", api_key.data())
+//! }
+//!
+//! let app = App::new()
+//!     .app_data(ApiKeyConfig::default().with_key("backup-job", "0123456789abcdef"))
+//!     .service(hello);
+//! ```
+
+use actix_web::{
+    HttpRequest, HttpResponse, HttpResponseBuilder,
+    body::BoxBody,
+    error::ResponseError,
+    http::{StatusCode, header::ContentType},
+};
+use serde::{Serialize, Serializer, ser::SerializeStruct};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use super::Authenticate;
+
+/// The error type that can occur during an authentication by API key.
+#[derive(Debug, Error)]
+pub enum ApiKeyError {
+    #[error("Unauthenticated")]
+    Unauthenticated,
+    #[error("No API key configuration registered in the application")]
+    NotConfigured,
+}
+
+impl Serialize for ApiKeyError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("Error", 2)?;
+
+        state.serialize_field("error", &self.to_string())?;
+        state.serialize_field("code", &self.status_code().as_u16())?;
+
+        state.end()
+    }
+}
+
+impl ResponseError for ApiKeyError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Unauthenticated => StatusCode::UNAUTHORIZED,
+            Self::NotConfigured => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse<BoxBody> {
+        let status_code = self.status_code();
+
+        HttpResponseBuilder::new(status_code)
+            .content_type(ContentType::json())
+            .json(self)
+    }
+}
+
+/// The header name and the valid keys, registered as application data.
+///
+/// Only the SHA-256 digests of the keys are kept.
+#[derive(Clone)]
+pub struct ApiKeyConfig {
+    header_name: String,
+    keys: Vec<(String, [u8; 32])>,
+}
+
+impl Default for ApiKeyConfig {
+    fn default() -> Self {
+        Self {
+            header_name: "X-API-Key".to_owned(),
+            keys: Vec::new(),
+        }
+    }
+}
+
+impl ApiKeyConfig {
+    /// Read the key from another header than `X-API-Key`.
+    #[must_use]
+    pub fn with_header_name(mut self, header_name: impl Into<String>) -> Self {
+        self.header_name = header_name.into();
+        self
+    }
+
+    /// Accept `key`, identified by `id` once authenticated.
+    #[must_use]
+    pub fn with_key(mut self, id: impl Into<String>, key: impl AsRef<[u8]>) -> Self {
+        self.keys.push((id.into(), Sha256::digest(key).into()));
+        self
+    }
+}
+
+/// Compare two digests in constant time.
+fn digests_equal(left: &[u8; 32], right: &[u8; 32]) -> bool {
+    left.iter()
+        .zip(right)
+        .fold(0_u8, |difference, (l, r)| difference | (l ^ r))
+        == 0
+}
+
+pub struct ApiKeyAuthenticator {
+    id: String,
+}
+
+impl Authenticate for ApiKeyAuthenticator {
+    type Output = String;
+    type Error = ApiKeyError;
+
+    fn authenticate(request: &HttpRequest) -> Result<Self, Self::Error> {
+        let config = request
+            .app_data::<ApiKeyConfig>()
+            .ok_or(ApiKeyError::NotConfigured)?;
+
+        let key = request
+            .headers()
+            .get(config.header_name.as_str())
+            .ok_or(ApiKeyError::Unauthenticated)?;
+        let digest: [u8; 32] = Sha256::digest(key.as_bytes()).into();
+
+        // every key is compared so that the time taken does not depend on
+        // which key matches, if any
+        let mut matched = None;
+        for (id, valid_digest) in &config.keys {
+            if digests_equal(&digest, valid_digest) {
+                matched = Some(id);
+            }
+        }
+
+        matched
+            .map(|id| Self { id: id.clone() })
+            .ok_or(ApiKeyError::Unauthenticated)
+    }
+
+    #[must_use]
+    fn data(&self) -> &Self::Output {
+        &self.id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{App, HttpResponse, Responder, get, http::StatusCode, test};
+
+    use super::{ApiKeyAuthenticator, ApiKeyConfig};
+    use crate::authentication::{Authenticate, Authenticated};
+
+    #[get("/key_id")]
+    async fn key_id(api_key: Authenticated<ApiKeyAuthenticator>) -> impl Responder {
+        HttpResponse::Ok().body(api_key.data().clone())
+    }
+
+    #[actix_web::test]
+    async fn authentication_by_api_key() {
+        let config = ApiKeyConfig::default()
+            .with_header_name("X-Internal-Key")
+            .with_key("first", "first-key")
+            .with_key("second", "second-key");
+        let app = test::init_service(App::new().app_data(config).service(key_id)).await;
+
+        let tests = [
+            (
+                Some(("X-Internal-Key", "first-key")),
+                StatusCode::OK,
+                "first",
+            ),
+            (
+                Some(("X-Internal-Key", "second-key")),
+                StatusCode::OK,
+                "second",
+            ),
+            (
+                Some(("X-Internal-Key", "third-key")),
+                StatusCode::UNAUTHORIZED,
+                "",
+            ),
+            (
+                Some(("X-API-Key", "first-key")),
+                StatusCode::UNAUTHORIZED,
+                "",
+            ),
+            (None, StatusCode::UNAUTHORIZED, ""),
+        ];
+
+        for (header, status_code, id) in tests {
+            let mut request = test::TestRequest::get().uri("/key_id");
+            if let Some(header) = header {
+                request = request.insert_header(header);
+            }
+            let result = test::call_service(&app, request.to_request()).await;
+            assert_eq!(
+                result.status(),
+                status_code,
+                "Failed for header: {header:?}"
+            );
+            if status_code == StatusCode::OK {
+                assert_eq!(test::read_body(result).await, id);
+            }
+        }
+    }
+}
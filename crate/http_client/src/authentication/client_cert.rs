@@ -0,0 +1,241 @@
+//! Client certificate authenticator.
+//!
+//! This authenticator identifies the client by the certificate it presented during a mutual TLS
+//! handshake terminated by the actix server. The TLS stream is only reachable when the
+//! connection is accepted, so the server must store the DER encoded certificate as a
+//! [`PeerCertificate`] in the connection data from `HttpServer::on_connect`, e.g. with rustls:
+//!
+//! ```rust,ignore
+//! HttpServer::new(app)
+//!     .on_connect(|connection, data| {
+//!         if let Some(tls) = connection.downcast_ref::<TlsStream<TcpStream>>() {
+//!             if let Some(certificate) = tls.get_ref().1.peer_certificates().and_then(|c| c.first()) {
+//!                 data.insert(PeerCertificate(certificate.0.clone()));
+//!             }
+//!         }
+//!     })
+//! ```
+//!
+//! The TLS acceptor is in charge of verifying the certificate: this authenticator only reads
+//! the identity from it, the common name by default or a subject alternative name if a
+//! [`ClientCertIdentity`] is registered as application data.
+
+use actix_web::{
+    HttpRequest, HttpResponse, HttpResponseBuilder,
+    body::BoxBody,
+    error::ResponseError,
+    http::{StatusCode, header::ContentType},
+};
+use serde::{Serialize, Serializer, ser::SerializeStruct};
+use thiserror::Error;
+use x509_cert::{
+    Certificate,
+    der::{
+        Any, Decode,
+        asn1::{Ia5StringRef, PrintableStringRef, Utf8StringRef},
+        oid::db::rfc4519::CN,
+    },
+    ext::pkix::{SubjectAltName, name::GeneralName},
+};
+
+use super::Authenticate;
+
+/// The error type that can occur during an authentication by client certificate.
+#[derive(Debug, Error)]
+pub enum ClientCertError {
+    #[error("Unauthenticated")]
+    Unauthenticated,
+    #[error("No {0} in the client certificate")]
+    MissingIdentity(&'static str),
+    #[error("Invalid client certificate: {0}")]
+    InvalidCertificate(#[from] x509_cert::der::Error),
+}
+
+impl Serialize for ClientCertError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("Error", 2)?;
+
+        state.serialize_field("error", &self.to_string())?;
+        state.serialize_field("code", &self.status_code().as_u16())?;
+
+        state.end()
+    }
+}
+
+impl ResponseError for ClientCertError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::UNAUTHORIZED
+    }
+
+    fn error_response(&self) -> HttpResponse<BoxBody> {
+        let status_code = self.status_code();
+
+        HttpResponseBuilder::new(status_code)
+            .content_type(ContentType::json())
+            .json(self)
+    }
+}
+
+/// The DER encoded certificate presented by the client, stored in the connection data.
+#[derive(Clone, Debug)]
+pub struct PeerCertificate(pub Vec<u8>);
+
+/// Which part of the client certificate identifies the client, registered as application data.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ClientCertIdentity {
+    /// The common name of the subject
+    #[default]
+    CommonName,
+    /// The first DNS name of the subject alternative names
+    DnsName,
+    /// The first email address of the subject alternative names
+    Email,
+    /// The first URI of the subject alternative names
+    Uri,
+}
+
+impl ClientCertIdentity {
+    const fn description(self) -> &'static str {
+        match self {
+            Self::CommonName => "common name",
+            Self::DnsName => "DNS name",
+            Self::Email => "email address",
+            Self::Uri => "URI",
+        }
+    }
+}
+
+/// Decode a directory string attribute value.
+fn directory_string(value: &Any) -> Option<String> {
+    Utf8StringRef::try_from(value)
+        .map(|s| s.as_str().to_owned())
+        .or_else(|_| PrintableStringRef::try_from(value).map(|s| s.as_str().to_owned()))
+        .or_else(|_| Ia5StringRef::try_from(value).map(|s| s.as_str().to_owned()))
+        .ok()
+}
+
+/// Read the identity of the client from its DER encoded certificate.
+fn client_identity(
+    certificate: &[u8],
+    identity: ClientCertIdentity,
+) -> Result<String, ClientCertError> {
+    let certificate = Certificate::from_der(certificate)?;
+    let tbs = &certificate.tbs_certificate;
+
+    let found = if identity == ClientCertIdentity::CommonName {
+        tbs.subject
+            .0
+            .iter()
+            .flat_map(|rdn| rdn.0.iter())
+            .find(|attribute| attribute.oid == CN)
+            .and_then(|attribute| directory_string(&attribute.value))
+    } else {
+        tbs.get::<SubjectAltName>()?.and_then(|(_, names)| {
+            names.0.iter().find_map(|name| match (identity, name) {
+                (ClientCertIdentity::DnsName, GeneralName::DnsName(value))
+                | (ClientCertIdentity::Email, GeneralName::Rfc822Name(value))
+                | (ClientCertIdentity::Uri, GeneralName::UniformResourceIdentifier(value)) => {
+                    Some(value.to_string())
+                }
+                _ => None,
+            })
+        })
+    };
+
+    found.ok_or_else(|| ClientCertError::MissingIdentity(identity.description()))
+}
+
+pub struct ClientCertAuthenticator {
+    identity: String,
+}
+
+impl Authenticate for ClientCertAuthenticator {
+    type Output = String;
+    type Error = ClientCertError;
+
+    fn authenticate(request: &HttpRequest) -> Result<Self, Self::Error> {
+        let certificate = request
+            .conn_data::<PeerCertificate>()
+            .ok_or(ClientCertError::Unauthenticated)?;
+        let identity = request
+            .app_data::<ClientCertIdentity>()
+            .copied()
+            .unwrap_or_default();
+
+        Ok(Self {
+            identity: client_identity(&certificate.0, identity)?,
+        })
+    }
+
+    #[must_use]
+    fn data(&self) -> &Self::Output {
+        &self.identity
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::panic_in_result_fn)]
+mod tests {
+    use actix_web::{App, HttpResponse, Responder, get, http::StatusCode};
+    use x509_cert::{
+        Certificate,
+        der::{DecodePem, Encode},
+    };
+
+    use super::{ClientCertAuthenticator, ClientCertError, ClientCertIdentity, client_identity};
+    use crate::authentication::{Authenticate, Authenticated};
+
+    const CLIENT_CERTIFICATE: &str = include_str!("../../test_data/client_certificate.pem");
+    const LEAF_CERTIFICATE: &str = include_str!("../../test_data/leaf_certificate.pem");
+
+    #[test]
+    fn identity_from_certificate() -> Result<(), x509_cert::der::Error> {
+        let der = Certificate::from_pem(CLIENT_CERTIFICATE)?.to_der()?;
+
+        let tests = [
+            (ClientCertIdentity::CommonName, "client"),
+            (ClientCertIdentity::DnsName, "client.example.com"),
+            (ClientCertIdentity::Email, "client@example.com"),
+            (ClientCertIdentity::Uri, "spiffe://example.com/client"),
+        ];
+        for (identity, expected) in tests {
+            assert_eq!(
+                client_identity(&der, identity).ok().as_deref(),
+                Some(expected),
+                "Failed for {identity:?}"
+            );
+        }
+
+        // the leaf certificate has no subject alternative name
+        let der = Certificate::from_pem(LEAF_CERTIFICATE)?.to_der()?;
+        assert!(matches!(
+            client_identity(&der, ClientCertIdentity::Email),
+            Err(ClientCertError::MissingIdentity(_))
+        ));
+        assert!(matches!(
+            client_identity(b"not a certificate", ClientCertIdentity::CommonName),
+            Err(ClientCertError::InvalidCertificate(_))
+        ));
+
+        Ok(())
+    }
+
+    #[get("/identity")]
+    async fn client_name(client: Authenticated<ClientCertAuthenticator>) -> impl Responder {
+        HttpResponse::Ok().body(client.data().clone())
+    }
+
+    #[actix_web::test]
+    async fn no_client_certificate() {
+        let app = actix_web::test::init_service(App::new().service(client_name)).await;
+
+        let request = actix_web::test::TestRequest::get()
+            .uri("/identity")
+            .to_request();
+        let result = actix_web::test::call_service(&app, request).await;
+        assert_eq!(result.status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -33,8 +33,14 @@
 //! }
 //! # }
 //! ```
+//!
+//! More than two authenticators are tried in order with [`Either3`] and [`Either4`], e.g.
+//! `Either3<Session<String>, JwtAuthenticator<String>, ApiKeyAuthenticator>`: the first one
+//! that succeeds wins and, if all of them fail, the error of the last one is returned.
+
+use std::future::{Ready, ready};
 
-use actix_web::Either;
+use actix_web::{Either, FromRequest, HttpRequest, dev::Payload};
 
 use super::{Authenticate, Authenticated};
 
@@ -57,6 +63,56 @@ where
     }
 }
 
+/// Define an enum extractor trying each of its authenticators in order.
+macro_rules! either_n {
+    ($(#[$doc:meta])* $name:ident { $($variant:ident: $generic:ident),+ }) => {
+        $(#[$doc])*
+        pub enum $name<$($generic: Authenticate),+> {
+            $($variant(Authenticated<$generic>)),+
+        }
+
+        impl<$($generic),+> FromRequest for $name<$($generic),+>
+        where
+            $($generic: Authenticate, $generic::Error: Into<actix_web::Error>),+
+        {
+            type Error = actix_web::Error;
+            type Future = Ready<Result<Self, Self::Error>>;
+
+            fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+                // the error of the last authenticator is kept
+                let result = Err(actix_web::error::ErrorUnauthorized("Unauthenticated"))
+                    $(.or_else(|_| {
+                        $generic::authenticate(req)
+                            .map(|value| Self::$variant(Authenticated(value)))
+                            .map_err(Into::into)
+                    }))+;
+                ready(result)
+            }
+        }
+
+        impl<$($generic),+, T> EitherExt<T> for $name<$($generic),+>
+        where
+            $($generic: Authenticate<Output = T>),+
+        {
+            fn data(&self) -> &T {
+                match self {
+                    $(Self::$variant(value) => value.data()),+
+                }
+            }
+        }
+    };
+}
+
+either_n!(
+    /// An extractor for a request authenticated by one of three authenticators, tried in order.
+    Either3 { First: A, Second: B, Third: C }
+);
+
+either_n!(
+    /// An extractor for a request authenticated by one of four authenticators, tried in order.
+    Either4 { First: A, Second: B, Third: C, Fourth: D }
+);
+
 #[cfg(test)]
 mod tests {
     use actix_http::Request;
@@ -68,7 +124,7 @@ mod tests {
         web::Bytes,
     };
 
-    use super::EitherExt;
+    use super::{Either3, EitherExt};
     use crate::authentication::{Authenticate, Authenticated};
 
     macro_rules! impl_authenticate {
@@ -98,7 +154,7 @@ mod tests {
         )+};
     }
 
-    impl_authenticate!(A, B);
+    impl_authenticate!(A, B, C);
 
     #[get("/")]
     async fn get_data(
@@ -107,10 +163,20 @@ mod tests {
         HttpResponse::Ok().body(authentication.data().to_string())
     }
 
+    #[get("/three")]
+    async fn get_data_of_three(authentication: Either3<A, B, C>) -> impl Responder {
+        let authenticator = match &authentication {
+            Either3::First(_) => "A",
+            Either3::Second(_) => "B",
+            Either3::Third(_) => "C",
+        };
+        HttpResponse::Ok().body(format!("{authenticator}:{}", authentication.data()))
+    }
+
     async fn create_app()
     -> impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = actix_web::Error>
     {
-        test::init_service(App::new().service(get_data)).await
+        test::init_service(App::new().service(get_data).service(get_data_of_three)).await
     }
 
     #[actix_web::test]
@@ -144,4 +210,30 @@ mod tests {
         let result = test::call_service(&app, request).await;
         assert!(result.status().is_client_error());
     }
+
+    #[actix_web::test]
+    async fn either3_authentication() {
+        let app = create_app().await;
+
+        let tests: [(&[(&str, &str)], &str); 3] = [
+            (&[("C", "testC"), ("B", "testB")], "B:testB"),
+            (&[("C", "testC")], "C:testC"),
+            (&[("A", "testA"), ("C", "testC")], "A:testA"),
+        ];
+        for (headers, expected) in tests {
+            let mut request = test::TestRequest::get().uri("/three");
+            for header in headers {
+                request = request.insert_header(*header);
+            }
+            let result = test::call_service(&app, request.to_request()).await;
+            assert!(result.status().is_success());
+
+            let body = test::read_body(result).await;
+            assert_eq!(body, Bytes::copy_from_slice(expected.as_bytes()));
+        }
+
+        let request = test::TestRequest::get().uri("/three").to_request();
+        let result = test::call_service(&app, request).await;
+        assert_eq!(result.status(), 401);
+    }
 }
@@ -0,0 +1,308 @@
+//! JWT authenticator.
+//!
+//! This authenticator verifies the JWT sent in the `Authorization: Bearer` header of a request.
+//! The key used to verify the tokens is a [`JwtConfig`] that must be registered beforehand as
+//! application data:
+//!
+//! ```rust,no_run
+//! use actix_web::{App, get};
+//! use cosmian_http_client::authentication::{
+//!     Authenticate, Authenticated,
+//!     jwt::{JwtAuthenticator, JwtConfig},
+//! };
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Claims {
+//!     sub: String,
+//! }
+//!
+//! #[get("/")]
+//! async fn hello(jwt: Authenticated<JwtAuthenticator<Claims>>) -> String {
+//!     format!("Hello, {}!", jwt.data().sub)
+//! }
+//!
+//! let app = App::new()
+//!     .app_data(JwtConfig::hs256(b"secret"))
+//!     .service(hello);
+//! ```
+
+use actix_web::{
+    HttpRequest, HttpResponse, HttpResponseBuilder,
+    body::BoxBody,
+    error::ResponseError,
+    http::{StatusCode, header::AUTHORIZATION, header::ContentType},
+};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, Validation, decode,
+    errors::{Error as JwtLibError, ErrorKind},
+};
+use serde::{Serialize, Serializer, de::DeserializeOwned, ser::SerializeStruct};
+use thiserror::Error;
+
+use super::Authenticate;
+
+/// The error type that can occur during an authentication by JWT.
+#[derive(Debug, Error)]
+pub enum JwtError {
+    #[error("Unauthenticated")]
+    Unauthenticated,
+    // the details are not sent to the client
+    #[error("Invalid token")]
+    InvalidToken(#[source] JwtLibError),
+    #[error("Invalid key: {0}")]
+    InvalidKey(JwtLibError),
+    #[error("No JWT configuration registered in the application")]
+    NotConfigured,
+}
+
+impl From<JwtLibError> for JwtError {
+    fn from(error: JwtLibError) -> Self {
+        match error.kind() {
+            ErrorKind::InvalidEcdsaKey
+            | ErrorKind::InvalidRsaKey(_)
+            | ErrorKind::RsaFailedSigning
+            | ErrorKind::InvalidKeyFormat
+            | ErrorKind::MissingAlgorithm => Self::InvalidKey(error),
+            _ => Self::InvalidToken(error),
+        }
+    }
+}
+
+impl Serialize for JwtError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("Error", 2)?;
+
+        state.serialize_field("error", &self.to_string())?;
+        state.serialize_field("code", &self.status_code().as_u16())?;
+
+        state.end()
+    }
+}
+
+impl ResponseError for JwtError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Unauthenticated | Self::InvalidToken(_) => StatusCode::UNAUTHORIZED,
+            Self::InvalidKey(_) | Self::NotConfigured => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse<BoxBody> {
+        let status_code = self.status_code();
+
+        HttpResponseBuilder::new(status_code)
+            .content_type(ContentType::json())
+            .json(self)
+    }
+}
+
+/// The key and the validation rules of the tokens, registered as application data.
+#[derive(Clone)]
+pub struct JwtConfig {
+    key: DecodingKey,
+    validation: Validation,
+}
+
+impl JwtConfig {
+    /// Verify HS256 tokens with a shared secret.
+    #[must_use]
+    pub fn hs256(secret: &[u8]) -> Self {
+        Self {
+            key: DecodingKey::from_secret(secret),
+            validation: Validation::new(Algorithm::HS256),
+        }
+    }
+
+    /// Verify RS256 tokens with a PEM encoded RSA public key.
+    ///
+    /// # Errors
+    /// Returns an error if the key is not a valid PEM encoded RSA public key.
+    pub fn rs256(public_key_pem: &[u8]) -> Result<Self, JwtError> {
+        Ok(Self {
+            key: DecodingKey::from_rsa_pem(public_key_pem).map_err(JwtError::InvalidKey)?,
+            validation: Validation::new(Algorithm::RS256),
+        })
+    }
+
+    /// Change the validation rules, e.g. to check the audience or the issuer.
+    ///
+    /// The expiry is always validated by default.
+    #[must_use]
+    pub fn with_validation(mut self, validation: Validation) -> Self {
+        self.validation = validation;
+        self
+    }
+}
+
+pub struct JwtAuthenticator<Claims: DeserializeOwned> {
+    claims: Claims,
+}
+
+impl<Claims: DeserializeOwned> Authenticate for JwtAuthenticator<Claims> {
+    type Output = Claims;
+    type Error = JwtError;
+
+    fn authenticate(request: &HttpRequest) -> Result<Self, Self::Error> {
+        let config = request
+            .app_data::<JwtConfig>()
+            .ok_or(JwtError::NotConfigured)?;
+
+        let token = request
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split_once(' '))
+            // the authentication scheme is case-insensitive (RFC 7235)
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("Bearer"))
+            .map(|(_, token)| token)
+            .ok_or(JwtError::Unauthenticated)?;
+
+        let claims = decode::<Claims>(token.trim(), &config.key, &config.validation)?.claims;
+
+        Ok(Self { claims })
+    }
+
+    #[must_use]
+    fn data(&self) -> &Self::Output {
+        &self.claims
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use actix_web::{App, HttpResponse, Responder, get, http::StatusCode, test};
+    use jsonwebtoken::{Algorithm, EncodingKey, Header, Validation, encode};
+    use serde::{Deserialize, Serialize};
+
+    use super::{JwtAuthenticator, JwtConfig};
+    use crate::authentication::{Authenticate, Authenticated};
+
+    const SECRET: &[u8] = b"secret";
+
+    #[derive(Serialize, Deserialize)]
+    struct Claims {
+        sub: String,
+        exp: u64,
+    }
+
+    #[get("/claims")]
+    async fn subject(jwt: Authenticated<JwtAuthenticator<Claims>>) -> impl Responder {
+        HttpResponse::Ok().body(jwt.data().sub.clone())
+    }
+
+    fn token(secret: &[u8], expires_in: i64) -> String {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs());
+        let claims = Claims {
+            sub: "user_id".to_owned(),
+            exp: now.saturating_add_signed(expires_in),
+        };
+        encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(secret),
+        )
+        .unwrap_or_default()
+    }
+
+    #[actix_web::test]
+    async fn authentication_by_jwt() {
+        let app = test::init_service(
+            App::new()
+                .app_data(JwtConfig::hs256(SECRET))
+                .service(subject),
+        )
+        .await;
+
+        let tests = [
+            (
+                Some(format!("Bearer {}", token(SECRET, 60))),
+                StatusCode::OK,
+            ),
+            (
+                Some(format!("bearer {}", token(SECRET, 60))),
+                StatusCode::OK,
+            ),
+            (
+                Some(format!("BEARER {}", token(SECRET, 60))),
+                StatusCode::OK,
+            ),
+            (None, StatusCode::UNAUTHORIZED),
+            (
+                Some(format!("Basic {}", token(SECRET, 60))),
+                StatusCode::UNAUTHORIZED,
+            ),
+            (
+                Some("Bearer not a jwt".to_owned()),
+                StatusCode::UNAUTHORIZED,
+            ),
+            (
+                Some(format!("Bearer {}", token(b"other secret", 60))),
+                StatusCode::UNAUTHORIZED,
+            ),
+            (
+                Some(format!("Bearer {}", token(SECRET, -3600))),
+                StatusCode::UNAUTHORIZED,
+            ),
+        ];
+
+        for (authorization, status_code) in tests {
+            let mut request = test::TestRequest::get().uri("/claims");
+            if let Some(authorization) = &authorization {
+                request = request.insert_header(("Authorization", authorization.clone()));
+            }
+            let result = test::call_service(&app, request.to_request()).await;
+            assert_eq!(
+                result.status(),
+                status_code,
+                "Failed for authorization: {authorization:?}"
+            );
+            if status_code == StatusCode::OK {
+                assert_eq!(test::read_body(result).await, "user_id");
+            }
+        }
+
+        // the details of an invalid token are not sent to the client
+        let request = test::TestRequest::get()
+            .uri("/claims")
+            .insert_header(("Authorization", format!("Bearer {}", token(SECRET, -3600))))
+            .to_request();
+        let result = test::call_service(&app, request).await;
+        assert_eq!(
+            test::read_body(result).await,
+            r#"{"error":"Invalid token","code":401}"#
+        );
+
+        // a misconfigured key is a server error
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.algorithms.clear();
+        let app = test::init_service(
+            App::new()
+                .app_data(JwtConfig::hs256(SECRET).with_validation(validation))
+                .service(subject),
+        )
+        .await;
+        let request = test::TestRequest::get()
+            .uri("/claims")
+            .insert_header(("Authorization", format!("Bearer {}", token(SECRET, 60))))
+            .to_request();
+        let result = test::call_service(&app, request).await;
+        assert_eq!(result.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        // no key registered
+        let app = test::init_service(App::new().service(subject)).await;
+        let request = test::TestRequest::get()
+            .uri("/claims")
+            .insert_header(("Authorization", format!("Bearer {}", token(SECRET, 60))))
+            .to_request();
+        let result = test::call_service(&app, request).await;
+        assert_eq!(result.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
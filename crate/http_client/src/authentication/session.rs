@@ -2,6 +2,11 @@
 //!
 //! This authenticator is built on top of the `actix_identity` and `actix_session` crates
 //! so it is required to have them in dependencies and setup in the application beforehand.
+//!
+//! Sessions may be given a maximum lifetime by registering a [`SessionTtl`] as application data:
+//! a session started longer ago is stopped and the request is unauthenticated.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use actix_identity::{
     Identity, IdentityExt,
@@ -11,7 +16,7 @@ use actix_web::{
     HttpMessage, HttpRequest, HttpResponse, HttpResponseBuilder, body::BoxBody,
     error::ResponseError, http::StatusCode, http::header::ContentType,
 };
-use serde::{Serialize, Serializer, de::DeserializeOwned, ser::SerializeStruct};
+use serde::{Deserialize, Serialize, Serializer, de::DeserializeOwned, ser::SerializeStruct};
 use serde_json::Error as SerdeError;
 use thiserror::Error;
 
@@ -67,9 +72,30 @@ impl ResponseError for SessionError {
     }
 }
 
+/// The maximum lifetime of a session, registered as application data.
+#[derive(Clone, Copy, Debug)]
+pub struct SessionTtl(pub Duration);
+
+/// What is stored in the identity: the session data and when the session started.
+#[derive(Serialize, Deserialize)]
+struct SessionPayload<T> {
+    data: T,
+    // seconds since the Unix epoch
+    issued_at: u64,
+}
+
+/// The current time as seconds since the Unix epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs())
+}
+
 pub struct Session<T: Serialize + DeserializeOwned> {
     data: T,
     identity: Option<Identity>,
+    issued_at: u64,
+    ttl: Option<Duration>,
 }
 
 impl<T: Serialize + DeserializeOwned> Session<T> {
@@ -84,14 +110,97 @@ impl<T: Serialize + DeserializeOwned> Session<T> {
     /// - The data cannot be serialized.
     /// - The session cannot be started.
     pub fn start(request: &HttpRequest, data: T) -> Result<Self, SessionError> {
-        let identity = Identity::login(&request.extensions(), serde_json::to_string(&data)?)?;
+        let payload = SessionPayload {
+            data,
+            issued_at: now(),
+        };
+        let identity = Identity::login(&request.extensions(), serde_json::to_string(&payload)?)?;
 
         Ok(Self {
-            data,
+            data: payload.data,
             identity: Some(identity),
+            issued_at: payload.issued_at,
+            ttl: request.app_data::<SessionTtl>().map(|ttl| ttl.0),
+        })
+    }
+
+    /// The time left before the session expires, if a `SessionTtl` is registered.
+    pub fn remaining_ttl(&self) -> Option<Duration> {
+        self.ttl.map(|ttl| {
+            let age = Duration::from_secs(now().saturating_sub(self.issued_at));
+            ttl.saturating_sub(age)
         })
     }
 
+    /// Replace the data attached to the session.
+    ///
+    /// The new data is stored in the identity, so the next requests of the session see it.
+    /// The session keeps its start time, so its TTL is not extended.
+    ///
+    /// The identity is stored by logging in again, like `rotate` does: the response carries a
+    /// new cookie and the cookie sent with the request is no longer valid. The login and visit
+    /// timestamps of the identity are reset too, which restarts the `login_deadline` and
+    /// `visit_deadline` of the `IdentityMiddleware` if any.
+    ///
+    /// # Arguments
+    /// * `request` - The request of the session.
+    /// * `data` - The new data to attach to the session.
+    ///
+    /// # Errors
+    /// This method can fail if:
+    /// - The session was stopped with `force_stop`: it cannot be updated.
+    /// - The data cannot be serialized.
+    /// - The identity cannot be stored.
+    pub fn update(&mut self, request: &HttpRequest, data: T) -> Result<(), SessionError> {
+        if self.is_stopped() {
+            return Err(SessionError::Unauthenticated);
+        }
+        let payload = SessionPayload {
+            data,
+            issued_at: self.issued_at,
+        };
+        self.identity = Some(Identity::login(
+            &request.extensions(),
+            serde_json::to_string(&payload)?,
+        )?);
+        self.data = payload.data;
+
+        Ok(())
+    }
+
+    /// Rotate the session identifier, keeping the data and the start time of the session.
+    ///
+    /// The session state is moved to a new key and the old one is deleted, so the response
+    /// carries a new cookie and the cookie sent with the request is no longer valid. This should
+    /// be done after a privilege change, such as a login, so that a session identifier set by
+    /// an attacker beforehand does not survive it.
+    ///
+    /// # Arguments
+    /// * `request` - The request of the session.
+    ///
+    /// # Errors
+    /// This method can fail if:
+    /// - The session was stopped with `force_stop`: it cannot be rotated.
+    /// - The data cannot be serialized.
+    /// - The identity cannot be stored.
+    pub fn rotate(&mut self, request: &HttpRequest) -> Result<(), SessionError> {
+        if self.is_stopped() {
+            return Err(SessionError::Unauthenticated);
+        }
+        let payload = SessionPayload {
+            data: &self.data,
+            issued_at: self.issued_at,
+        };
+        // logging in again renews the session key, whereas logging out first
+        // would purge the session for the rest of the request
+        self.identity = Some(Identity::login(
+            &request.extensions(),
+            serde_json::to_string(&payload)?,
+        )?);
+
+        Ok(())
+    }
+
     /// Stop the session forcefully.
     ///
     /// Any further requests will be unauthenticated.
@@ -112,17 +221,34 @@ impl<T: Serialize + DeserializeOwned> Authenticate for Session<T> {
     type Error = SessionError;
 
     fn authenticate(request: &HttpRequest) -> Result<Self, Self::Error> {
-        request
-            .get_identity()
-            .map_err(Into::into)
-            .and_then(|identity| {
-                let data = serde_json::from_str(identity.id()?.as_str())?;
+        let mut identity = request.get_identity()?;
+        let id = identity.id()?;
+        let payload = if let Ok(payload) = serde_json::from_str::<SessionPayload<T>>(&id) {
+            payload
+        } else {
+            // sessions started before the payload held a start time carry the
+            // bare data: they are stamped as started the first time they are
+            // read, rather than expired at once
+            let payload = SessionPayload {
+                data: serde_json::from_str(&id)?,
+                issued_at: now(),
+            };
+            identity = Identity::login(&request.extensions(), serde_json::to_string(&payload)?)?;
+            payload
+        };
+
+        let mut session = Self {
+            data: payload.data,
+            identity: Some(identity),
+            issued_at: payload.issued_at,
+            ttl: request.app_data::<SessionTtl>().map(|ttl| ttl.0),
+        };
+        if session.remaining_ttl().is_some_and(|ttl| ttl.is_zero()) {
+            session.force_stop();
+            return Err(SessionError::Unauthenticated);
+        }
 
-                Ok(Self {
-                    data,
-                    identity: Some(identity),
-                })
-            })
+        Ok(session)
     }
 
     #[must_use]
@@ -163,6 +289,44 @@ mod tests {
         HttpResponse::Ok().json(session.data())
     }
 
+    #[get("/remaining_ttl")]
+    async fn remaining_ttl(session: Authenticated<Session<String>>) -> impl Responder {
+        HttpResponse::Ok().json(session.remaining_ttl().map(|ttl| ttl.as_secs()))
+    }
+
+    #[post("/update_session")]
+    async fn update_session(
+        request: HttpRequest,
+        mut session: Authenticated<Session<String>>,
+    ) -> impl Responder {
+        let status_code = session
+            .update(&request, "updated_user_id".to_owned())
+            .map_or(StatusCode::INTERNAL_SERVER_ERROR, |()| StatusCode::OK);
+
+        HttpResponse::new(status_code)
+    }
+
+    #[post("/rotate_session")]
+    async fn rotate_session(
+        request: HttpRequest,
+        mut session: Authenticated<Session<String>>,
+    ) -> impl Responder {
+        let status_code = session
+            .rotate(&request)
+            .map_or(StatusCode::INTERNAL_SERVER_ERROR, |()| StatusCode::OK);
+
+        HttpResponse::new(status_code)
+    }
+
+    #[post("/start_legacy_session")]
+    async fn start_legacy_session(request: HttpRequest) -> impl Responder {
+        // the bare data, as stored before the payload held a start time
+        let status_code = Identity::login(&request.extensions(), "\"user_id\"".to_owned())
+            .map_or(StatusCode::INTERNAL_SERVER_ERROR, |_| StatusCode::OK);
+
+        HttpResponse::new(status_code)
+    }
+
     #[post("/stop_session")]
     async fn stop_session(mut session: Authenticated<Session<String>>) -> impl Responder {
         session.force_stop();
@@ -170,18 +334,23 @@ mod tests {
         HttpResponse::Ok()
     }
 
-    async fn create_app()
-    -> impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = actix_web::Error>
+    async fn create_app(
+        ttl: Option<SessionTtl>,
+    ) -> impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = actix_web::Error>
     {
+        let app = ttl.map_or_else(App::new, |ttl| App::new().app_data(ttl));
         test::init_service(
-            App::new()
-                .wrap(IdentityMiddleware::default())
+            app.wrap(IdentityMiddleware::default())
                 .wrap(SessionMiddleware::new(
                     MockSessionStore::default(),
                     Key::generate(),
                 ))
                 .service(start_session)
+                .service(start_legacy_session)
                 .service(session_data)
+                .service(remaining_ttl)
+                .service(update_session)
+                .service(rotate_session)
                 .service(stop_session),
         )
         .await
@@ -189,7 +358,7 @@ mod tests {
 
     #[actix_web::test]
     async fn authentication_by_session() {
-        let app = create_app().await;
+        let app = create_app(None).await;
 
         let request = test::TestRequest::post().uri("/start_session").to_request();
         let result = test::call_service(&app, request).await;
@@ -218,4 +387,167 @@ mod tests {
             assert_eq!(result.status(), status_code, "Failed for uri: {uri}");
         }
     }
+
+    #[actix_web::test]
+    async fn session_ttl() {
+        for (ttl, status_code) in [
+            (None, StatusCode::OK),
+            (Some(Duration::from_secs(3600)), StatusCode::OK),
+            (Some(Duration::ZERO), StatusCode::UNAUTHORIZED),
+        ] {
+            let app = create_app(ttl.map(SessionTtl)).await;
+
+            let request = test::TestRequest::post().uri("/start_session").to_request();
+            let result = test::call_service(&app, request).await;
+            let cookies = result.response().cookies().collect::<Vec<_>>();
+            assert_eq!(cookies.len(), 1);
+
+            #[allow(clippy::indexing_slicing)]
+            let cookie = &cookies[0];
+
+            let request = test::TestRequest::get()
+                .uri("/remaining_ttl")
+                .cookie(cookie.clone())
+                .to_request();
+            let result = test::call_service(&app, request).await;
+            assert_eq!(result.status(), status_code, "Failed for ttl: {ttl:?}");
+            if status_code == StatusCode::OK {
+                let body = test::read_body(result).await;
+                let remaining = serde_json::from_slice::<Option<u64>>(&body).ok().flatten();
+                // a second may have elapsed since the session started
+                let expected = ttl.map(|ttl| ttl.as_secs());
+                assert!(
+                    remaining == expected || remaining.map(|r| r + 1) == expected,
+                    "Failed for ttl: {ttl:?}, remaining: {remaining:?}"
+                );
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn legacy_session_ttl() {
+        let app = create_app(Some(SessionTtl(Duration::from_secs(3600)))).await;
+
+        let request = test::TestRequest::post()
+            .uri("/start_legacy_session")
+            .to_request();
+        let result = test::call_service(&app, request).await;
+        let cookies = result.response().cookies().collect::<Vec<_>>();
+        assert_eq!(cookies.len(), 1);
+
+        #[allow(clippy::indexing_slicing)]
+        let cookie = cookies[0].clone().into_owned();
+
+        // the legacy session is stamped as started now, and its cookie renewed
+        let request = test::TestRequest::get()
+            .uri("/remaining_ttl")
+            .cookie(cookie)
+            .to_request();
+        let result = test::call_service(&app, request).await;
+        assert!(result.status().is_success());
+        let cookies = result.response().cookies().collect::<Vec<_>>();
+        assert_eq!(cookies.len(), 1);
+
+        #[allow(clippy::indexing_slicing)]
+        let cookie = cookies[0].clone().into_owned();
+        let body = test::read_body(result).await;
+        let remaining = serde_json::from_slice::<Option<u64>>(&body).ok().flatten();
+        assert!(
+            matches!(remaining, Some(3599 | 3600)),
+            "remaining: {remaining:?}"
+        );
+
+        let request = test::TestRequest::get()
+            .uri("/session_data")
+            .cookie(cookie)
+            .to_request();
+        let result = test::call_service(&app, request).await;
+        assert!(result.status().is_success());
+        assert_eq!(test::read_body(result).await, "\"user_id\"");
+    }
+
+    #[actix_web::test]
+    async fn session_update() {
+        let app = create_app(None).await;
+
+        let request = test::TestRequest::post().uri("/start_session").to_request();
+        let result = test::call_service(&app, request).await;
+        let cookies = result.response().cookies().collect::<Vec<_>>();
+        assert_eq!(cookies.len(), 1);
+
+        #[allow(clippy::indexing_slicing)]
+        let old_cookie = cookies[0].clone().into_owned();
+
+        let request = test::TestRequest::post()
+            .uri("/update_session")
+            .cookie(old_cookie.clone())
+            .to_request();
+        let result = test::call_service(&app, request).await;
+        assert!(result.status().is_success());
+
+        // the session is renewed along with the identity
+        let cookies = result.response().cookies().collect::<Vec<_>>();
+        assert_eq!(cookies.len(), 1);
+
+        #[allow(clippy::indexing_slicing)]
+        let cookie = &cookies[0];
+
+        let request = test::TestRequest::get()
+            .uri("/session_data")
+            .cookie(cookie.clone())
+            .to_request();
+        let result = test::call_service(&app, request).await;
+        assert!(result.status().is_success());
+        assert_eq!(test::read_body(result).await, "\"updated_user_id\"");
+
+        let request = test::TestRequest::get()
+            .uri("/session_data")
+            .cookie(old_cookie)
+            .to_request();
+        let result = test::call_service(&app, request).await;
+        assert_eq!(result.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn session_rotation() {
+        let app = create_app(None).await;
+
+        let request = test::TestRequest::post().uri("/start_session").to_request();
+        let result = test::call_service(&app, request).await;
+        let cookies = result.response().cookies().collect::<Vec<_>>();
+        assert_eq!(cookies.len(), 1);
+
+        #[allow(clippy::indexing_slicing)]
+        let old_cookie = cookies[0].clone().into_owned();
+
+        let request = test::TestRequest::post()
+            .uri("/rotate_session")
+            .cookie(old_cookie.clone())
+            .to_request();
+        let result = test::call_service(&app, request).await;
+        assert!(result.status().is_success());
+
+        let cookies = result.response().cookies().collect::<Vec<_>>();
+        assert_eq!(cookies.len(), 1);
+
+        #[allow(clippy::indexing_slicing)]
+        let new_cookie = cookies[0].clone().into_owned();
+        assert_ne!(new_cookie.value(), old_cookie.value());
+
+        // the data is kept under the new identifier only
+        let request = test::TestRequest::get()
+            .uri("/session_data")
+            .cookie(new_cookie)
+            .to_request();
+        let result = test::call_service(&app, request).await;
+        assert!(result.status().is_success());
+        assert_eq!(test::read_body(result).await, "\"user_id\"");
+
+        let request = test::TestRequest::get()
+            .uri("/session_data")
+            .cookie(old_cookie)
+            .to_request();
+        let result = test::call_service(&app, request).await;
+        assert_eq!(result.status(), StatusCode::UNAUTHORIZED);
+    }
 }
@@ -0,0 +1,152 @@
+//! A blocking HTTP(S) client, for programs that do not run an async runtime.
+//!
+//! [`BlockingHttpClient`] is set up from the same [`HttpClientConfig`], with
+//! the same TLS, client identity, header and timeout settings, as the async
+//! [`HttpClient`](crate::HttpClient). The `retry` configuration is not
+//! supported: requests are sent once.
+//!
+//! Like any `reqwest` blocking client, it must not be used, created or
+//! dropped from within an async runtime.
+
+use reqwest::blocking::{Client, Response};
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+    HttpClientConfig, HttpClientError,
+    error::result::{HttpClientResult, HttpClientResultHelper},
+    http_client::configure_builder,
+    http_client_bail,
+};
+
+#[derive(Clone)]
+pub struct BlockingHttpClient {
+    pub server_url: String,
+    client: Client,
+}
+
+impl BlockingHttpClient {
+    /// Instantiate a new blocking HTTP(S) Client
+    /// # Errors
+    /// Will return an error if the client cannot be instantiated
+    pub fn instantiate(http_conf: &HttpClientConfig) -> Result<Self, HttpClientError> {
+        http_conf.validate()?;
+
+        let server_url = http_conf.base_url()?;
+
+        Ok(Self {
+            client: configure_builder::<reqwest::blocking::ClientBuilder>(http_conf)?
+                .build()
+                .context("Reqwest blocking client builder")?,
+            server_url,
+        })
+    }
+
+    /// The `reqwest` blocking client used to send the requests
+    #[must_use]
+    pub const fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Build the URL of `path` on the server
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.server_url, path.trim_start_matches('/'))
+    }
+
+    /// Send a GET request to `path` on the server and deserialize the JSON
+    /// response
+    /// # Errors
+    /// Will return an error if the request fails, the server does not return
+    /// a success status, as a `HttpClientError::HttpStatus`, or the response
+    /// cannot be deserialized
+    pub fn get_json<R: DeserializeOwned>(&self, path: &str) -> HttpClientResult<R> {
+        json_response(self.client.get(self.url(path)).send()?)
+    }
+
+    /// Send a POST request with a JSON body to `path` on the server and
+    /// deserialize the JSON response
+    /// # Errors
+    /// Will return an error if the request fails, the server does not return
+    /// a success status, as a `HttpClientError::HttpStatus`, or the response
+    /// cannot be deserialized
+    pub fn post_json<B: Serialize, R: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> HttpClientResult<R> {
+        json_response(self.client.post(self.url(path)).json(body).send()?)
+    }
+}
+
+/// Check the status of a response and deserialize its JSON body
+fn json_response<R: DeserializeOwned>(response: Response) -> HttpClientResult<R> {
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().unwrap_or_default();
+        http_client_bail!(HttpClientError::HttpStatus {
+            code: status.as_u16(),
+            body
+        })
+    }
+    response
+        .json()
+        .map_err(|e| HttpClientError::ResponseFailed(format!("invalid JSON response: {e}")))
+}
+
+#[cfg(test)]
+#[allow(clippy::panic_in_result_fn)]
+mod tests {
+    use std::{sync::mpsc, thread};
+
+    use actix_web::{HttpResponse, web};
+    use serde_json::{Value, json};
+
+    use super::BlockingHttpClient;
+    use crate::{HttpClientConfig, HttpClientError, tests::test_server::spawn_test_server};
+
+    #[test]
+    fn blocking_json_requests() -> Result<(), anyhow::Error> {
+        // the test server runs on its own thread, outside of the blocking calls
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            actix_web::rt::System::new().block_on(async move {
+                let address = spawn_test_server(|config| {
+                    config
+                        .route(
+                            "/get",
+                            web::get()
+                                .to(|| async { HttpResponse::Ok().json(json!({"key": "value"})) }),
+                        )
+                        .route(
+                            "/echo",
+                            web::post().to(|body: web::Json<Value>| async move {
+                                HttpResponse::Ok().json(body.into_inner())
+                            }),
+                        );
+                });
+                drop(sender.send(address.map_err(|e| e.to_string())));
+                std::future::pending::<()>().await;
+            });
+        });
+        let address = receiver.recv()?.map_err(anyhow::Error::msg)?;
+
+        let http_client = BlockingHttpClient::instantiate(&HttpClientConfig {
+            server_url: format!("http://{address}/"),
+            ..Default::default()
+        })?;
+
+        let response: Value = http_client.get_json("/get")?;
+        assert_eq!(response, json!({"key": "value"}));
+
+        let body = json!({"id": 1});
+        let response: Value = http_client.post_json("echo", &body)?;
+        assert_eq!(response, body);
+
+        let result = http_client.get_json::<Value>("/missing");
+        assert!(matches!(
+            result,
+            Err(HttpClientError::HttpStatus { code: 404, .. })
+        ));
+
+        Ok(())
+    }
+}
@@ -4,6 +4,11 @@ use rustls::{
     Certificate, Error as RustTLSError, ServerName,
     client::{ServerCertVerified, ServerCertVerifier},
 };
+use sha2::{Digest, Sha256};
+use x509_cert::{
+    Certificate as X509Certificate,
+    der::{Decode, Encode},
+};
 
 /// A TLS verifier adding the ability to match the leaf certificate with a
 /// trusted one.
@@ -55,6 +60,66 @@ impl ServerCertVerifier for LeafCertificateVerifier {
     }
 }
 
+/// A TLS verifier adding the ability to match the public key of the leaf
+/// certificate with a set of trusted SHA-256 pins.
+pub(crate) struct PinnedKeyVerifier {
+    // The SHA-256 digests of the `SubjectPublicKeyInfo` we accept
+    pinned_spki_sha256: Vec<[u8; 32]>,
+    // A default verifier to run anyway
+    default_verifier: Arc<dyn ServerCertVerifier>,
+}
+
+impl PinnedKeyVerifier {
+    pub(crate) fn new(
+        pinned_spki_sha256: Vec<[u8; 32]>,
+        default_verifier: Arc<dyn ServerCertVerifier>,
+    ) -> Self {
+        Self {
+            pinned_spki_sha256,
+            default_verifier,
+        }
+    }
+}
+
+/// Compute the SHA-256 digest of the `SubjectPublicKeyInfo` of a DER
+/// certificate.
+pub(crate) fn spki_sha256(certificate: &Certificate) -> Result<[u8; 32], RustTLSError> {
+    let spki = X509Certificate::from_der(&certificate.0)
+        .and_then(|certificate| certificate.tbs_certificate.subject_public_key_info.to_der())
+        .map_err(|e| RustTLSError::General(format!("Invalid leaf certificate: {e}")))?;
+    Ok(Sha256::digest(spki).into())
+}
+
+impl ServerCertVerifier for PinnedKeyVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,              // end_entity
+        intermediates: &[Certificate],         // intermediates
+        server_name: &ServerName,              // server_name
+        scts: &mut dyn Iterator<Item = &[u8]>, // scts
+        ocsp_response: &[u8],                  // ocsp_response
+        now: SystemTime,                       // now
+    ) -> Result<ServerCertVerified, RustTLSError> {
+        // Verify the public key of the leaf certificate
+        let spki_sha256 = spki_sha256(end_entity)?;
+        if !self.pinned_spki_sha256.contains(&spki_sha256) {
+            return Err(RustTLSError::General(
+                "Leaf certificate public key doesn't match any pinned one".to_owned(),
+            ));
+        }
+
+        // Now proceed with typical verifications
+        self.default_verifier.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            scts,
+            ocsp_response,
+            now,
+        )
+    }
+}
+
 /// Remove all verifications
 pub(crate) struct NoVerifier;
 
@@ -71,3 +136,49 @@ impl ServerCertVerifier for NoVerifier {
         Ok(ServerCertVerified::assertion())
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::panic_in_result_fn)]
+mod tests {
+    use std::{sync::Arc, time::SystemTime};
+
+    use base64::{Engine, engine::general_purpose::STANDARD};
+    use rustls::{Certificate, Error as RustTLSError, ServerName, client::ServerCertVerifier};
+    use x509_cert::{
+        Certificate as X509Certificate,
+        der::{DecodePem, Encode},
+    };
+
+    use super::{NoVerifier, PinnedKeyVerifier, spki_sha256};
+
+    const LEAF_CERTIFICATE: &str = include_str!("../test_data/leaf_certificate.pem");
+    // openssl x509 -pubkey -noout | openssl pkey -pubin -outform der | openssl
+    // dgst -sha256 -binary | base64
+    const LEAF_SPKI_SHA256: &str = "bKno/uLkqbSImhbU/Vy0yQsKWfTpTpuz/hnVnHQYLxk=";
+
+    #[test]
+    fn pinned_key_verifier() -> Result<(), Box<dyn std::error::Error>> {
+        let leaf_cert = Certificate(X509Certificate::from_pem(LEAF_CERTIFICATE)?.to_der()?);
+        let leaf_spki_sha256 = spki_sha256(&leaf_cert)?;
+        assert_eq!(STANDARD.encode(leaf_spki_sha256), LEAF_SPKI_SHA256);
+
+        let server_name = ServerName::try_from("localhost")?;
+        let verify = |pins: Vec<[u8; 32]>| {
+            PinnedKeyVerifier::new(pins, Arc::new(NoVerifier)).verify_server_cert(
+                &leaf_cert,
+                &[],
+                &server_name,
+                &mut std::iter::empty(),
+                &[],
+                SystemTime::now(),
+            )
+        };
+        verify(vec![[0; 32], leaf_spki_sha256])?;
+        assert!(matches!(
+            verify(vec![[0; 32]]),
+            Err(RustTLSError::General(_))
+        ));
+
+        Ok(())
+    }
+}
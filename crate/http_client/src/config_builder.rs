@@ -0,0 +1,317 @@
+use std::time::Duration;
+
+use crate::{
+    CipherPreference, CompressionAlgo, HttpClientConfig, HttpClientError, HttpVersionPref,
+    Oauth2LoginConfig, RedirectPolicy, RetryConfig, TlsBackend,
+};
+
+/// A builder of `HttpClientConfig`, starting from the default configuration.
+///
+/// ```no_run
+/// use cosmian_http_client::{HttpClient, HttpClientConfig};
+///
+/// # fn build() -> Result<HttpClient, cosmian_http_client::HttpClientError> {
+/// let http_conf = HttpClientConfig::builder()
+///     .server_url("https://kms.example.com")
+///     .bearer_token("token")
+///     .build()?;
+/// HttpClient::instantiate(&http_conf)
+/// # }
+/// ```
+#[derive(Default)]
+pub struct HttpClientConfigBuilder {
+    config: HttpClientConfig,
+}
+
+impl HttpClientConfigBuilder {
+    /// Set the URL of the server
+    #[must_use]
+    pub fn server_url(mut self, server_url: impl Into<String>) -> Self {
+        self.config.server_url = server_url.into();
+        self
+    }
+
+    /// Accept invalid or self-signed server certificates
+    #[must_use]
+    pub const fn accept_invalid_certs(mut self, accept_invalid_certs: bool) -> Self {
+        self.config.accept_invalid_certs = accept_invalid_certs;
+        self
+    }
+
+    /// Set the leaf certificate expected from a server running inside a tee,
+    /// either PEM or base64 encoded DER
+    #[must_use]
+    pub fn verified_cert(mut self, verified_cert: impl Into<String>) -> Self {
+        self.config.verified_cert = Some(verified_cert.into());
+        self
+    }
+
+    /// Authenticate with a bearer token
+    #[must_use]
+    pub fn bearer_token(mut self, access_token: impl Into<String>) -> Self {
+        self.config.access_token = Some(access_token.into());
+        self
+    }
+
+    /// Authenticate with a PKCS12 client certificate
+    #[must_use]
+    pub fn pkcs12(mut self, path: impl Into<String>, password: impl Into<String>) -> Self {
+        self.config.ssl_client_pkcs12_path = Some(path.into());
+        self.config.ssl_client_pkcs12_password = Some(password.into());
+        self
+    }
+
+    /// Authenticate with a PKCS12 client certificate held in memory
+    #[must_use]
+    pub fn pkcs12_bytes(mut self, pkcs12: Vec<u8>, password: impl Into<String>) -> Self {
+        self.config.ssl_client_pkcs12_bytes = Some(pkcs12);
+        self.config.ssl_client_pkcs12_password = Some(password.into());
+        self
+    }
+
+    /// Authenticate with a PEM client certificate chain and its PKCS#8 PEM
+    /// private key
+    #[must_use]
+    pub fn pem(mut self, cert_path: impl Into<String>, key_path: impl Into<String>) -> Self {
+        self.config.ssl_client_pem_cert_path = Some(cert_path.into());
+        self.config.ssl_client_pem_key_path = Some(key_path.into());
+        self
+    }
+
+    /// Set the password of the PEM private key when it is encrypted
+    #[must_use]
+    pub fn pem_key_password(mut self, password: impl Into<String>) -> Self {
+        self.config.ssl_client_pem_key_password = Some(password.into());
+        self
+    }
+
+    /// Send all the requests through an HTTP or SOCKS5 proxy
+    #[must_use]
+    pub fn proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.config.proxy = Some(proxy.into());
+        self
+    }
+
+    /// Set the hosts reached without going through the proxy
+    #[must_use]
+    pub fn proxy_exclusion_list(mut self, hosts: Vec<String>) -> Self {
+        self.config.proxy_exclusion_list = Some(hosts);
+        self
+    }
+
+    /// Use the proxies of the environment variables when no proxy is set,
+    /// the default
+    #[must_use]
+    pub const fn use_env_proxy(mut self, use_env_proxy: bool) -> Self {
+        self.config.use_env_proxy = use_env_proxy;
+        self
+    }
+
+    /// Set the secret sent in the `DatabaseSecret` header
+    #[must_use]
+    pub fn database_secret(mut self, database_secret: impl Into<String>) -> Self {
+        self.config.database_secret = Some(database_secret.into());
+        self
+    }
+
+    /// Set the `OAuth2` login configuration
+    #[must_use]
+    pub fn oauth2_conf(mut self, oauth2_conf: Oauth2LoginConfig) -> Self {
+        self.config.oauth2_conf = Some(oauth2_conf);
+        self
+    }
+
+    /// Select the TLS implementation
+    #[must_use]
+    pub const fn tls_backend(mut self, tls_backend: TlsBackend) -> Self {
+        self.config.tls_backend = tls_backend;
+        self
+    }
+
+    /// Set the colon-separated list of cipher suites offered by the client
+    #[must_use]
+    pub fn cipher_suites(mut self, cipher_suites: impl Into<String>) -> Self {
+        self.config.cipher_suites = Some(cipher_suites.into());
+        self
+    }
+
+    /// Offer the AES-GCM or the ChaCha20-Poly1305 cipher suites first
+    #[must_use]
+    pub const fn cipher_preference(mut self, cipher_preference: CipherPreference) -> Self {
+        self.config.cipher_preference = Some(cipher_preference);
+        self
+    }
+
+    /// Pin the server public key with base64 encoded SHA-256 digests of its
+    /// `SubjectPublicKeyInfo`
+    #[must_use]
+    pub fn pinned_spki_sha256(mut self, pins: Vec<String>) -> Self {
+        self.config.pinned_spki_sha256 = Some(pins);
+        self
+    }
+
+    /// Set the timeout of a whole request
+    #[must_use]
+    pub const fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.config.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the timeout of the connection phase
+    #[must_use]
+    pub const fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.config.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Set how redirects are followed
+    #[must_use]
+    pub const fn redirect_policy(mut self, redirect_policy: RedirectPolicy) -> Self {
+        self.config.redirect_policy = Some(redirect_policy);
+        self
+    }
+
+    /// Set the maximum number of idle connections kept open per host
+    #[must_use]
+    pub const fn pool_max_idle_per_host(mut self, max_idle: usize) -> Self {
+        self.config.pool_max_idle_per_host = Some(max_idle);
+        self
+    }
+
+    /// Set how long an idle connection is kept open
+    #[must_use]
+    pub const fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.config.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Set how the JSON helpers retry failed requests
+    #[must_use]
+    pub fn retry(mut self, retry: RetryConfig) -> Self {
+        self.config.retry = Some(retry);
+        self
+    }
+
+    /// Set the User-Agent header sent with every request
+    #[must_use]
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.config.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Set the HTTP version spoken by the client
+    #[must_use]
+    pub const fn http_version(mut self, http_version: HttpVersionPref) -> Self {
+        self.config.http_version = Some(http_version);
+        self
+    }
+
+    /// Set the compressions of the response bodies accepted by the client,
+    /// which requires the `compression` feature
+    #[must_use]
+    pub fn accept_compression(mut self, algorithms: Vec<CompressionAlgo>) -> Self {
+        self.config.accept_compression = Some(algorithms);
+        self
+    }
+
+    /// Set the path requested to check that the server is reachable
+    #[must_use]
+    pub fn health_path(mut self, health_path: impl Into<String>) -> Self {
+        self.config.health_path = Some(health_path.into());
+        self
+    }
+
+    /// Trust the PEM certificates, or the PEM files, in `certificates` in
+    /// addition to the default root certificates
+    #[must_use]
+    pub fn extra_root_certificates(mut self, certificates: Vec<String>) -> Self {
+        self.config.extra_root_certificates = Some(certificates);
+        self
+    }
+
+    /// Use `hostname` in the TLS handshake and the Host header while
+    /// connecting to the IP address of the server URL
+    #[must_use]
+    pub fn tls_sni_hostname(mut self, hostname: impl Into<String>) -> Self {
+        self.config.tls_sni_hostname = Some(hostname.into());
+        self
+    }
+
+    /// Validate and return the configuration
+    /// # Errors
+    /// Will return an error if options that cannot be used together are set
+    pub fn build(self) -> Result<HttpClientConfig, HttpClientError> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::panic_in_result_fn)]
+mod tests {
+    use crate::{HttpClientConfig, HttpClientError, TlsBackend};
+
+    #[test]
+    fn build_config() -> Result<(), HttpClientError> {
+        let http_conf = HttpClientConfig::builder()
+            .server_url("https://localhost:9998")
+            .bearer_token("token")
+            .pkcs12("client.p12", "secret")
+            .build()?;
+        assert_eq!(http_conf, HttpClientConfig {
+            server_url: "https://localhost:9998".to_owned(),
+            access_token: Some("token".to_owned()),
+            ssl_client_pkcs12_path: Some("client.p12".to_owned()),
+            ssl_client_pkcs12_password: Some("secret".to_owned()),
+            ..Default::default()
+        });
+
+        let result = HttpClientConfig::builder()
+            .pkcs12("client.p12", "secret")
+            .tls_backend(TlsBackend::Rustls)
+            .build();
+        assert!(matches!(result, Err(HttpClientError::NotSupported(_))));
+
+        let http_conf = HttpClientConfig::builder()
+            .pem("client.pem", "client.key")
+            .pem_key_password("secret")
+            .proxy("socks5h://proxy.example.com:1080")
+            .tls_backend(TlsBackend::Rustls)
+            .build()?;
+        assert_eq!(http_conf, HttpClientConfig {
+            ssl_client_pem_cert_path: Some("client.pem".to_owned()),
+            ssl_client_pem_key_path: Some("client.key".to_owned()),
+            ssl_client_pem_key_password: Some("secret".to_owned()),
+            proxy: Some("socks5h://proxy.example.com:1080".to_owned()),
+            tls_backend: TlsBackend::Rustls,
+            ..Default::default()
+        });
+
+        let result = HttpClientConfig::builder()
+            .pkcs12("client.p12", "secret")
+            .pem("client.pem", "client.key")
+            .build();
+        assert!(matches!(result, Err(HttpClientError::NotSupported(_))));
+
+        let result = HttpClientConfig {
+            ssl_client_pem_cert_path: Some("client.pem".to_owned()),
+            ..Default::default()
+        }
+        .validate();
+        assert!(matches!(result, Err(HttpClientError::Conversion(_))));
+
+        for proxy in [
+            "http://[invalid",
+            "ftp://proxy.example.com",
+            "proxy.example.com",
+        ] {
+            let result = HttpClientConfig::builder().proxy(proxy).build();
+            assert!(
+                matches!(result, Err(HttpClientError::Conversion(_))),
+                "Failed for {proxy:?}"
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+//! (De)serialization of the `Duration` fields of the configuration.
+//!
+//! A duration is read either as a number, of seconds or of milliseconds
+//! depending on the field, or as a human-readable string such as `30s`,
+//! `1m 30s` or `500ms`. It is written as a number when it is a whole number
+//! of the unit, for compatibility, and as a human-readable string otherwise,
+//! so that a round trip never loses precision.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+/// A duration as written in a configuration file
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HumanDuration {
+    Number(u64),
+    Text(String),
+}
+
+fn serialize<S: Serializer>(
+    duration: Duration,
+    unit: Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    if duration.as_nanos() % unit.as_nanos() == 0 {
+        let count = duration.as_nanos() / unit.as_nanos();
+        serializer.serialize_u64(u64::try_from(count).unwrap_or(u64::MAX))
+    } else {
+        serializer.collect_str(&humantime::format_duration(duration))
+    }
+}
+
+fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    from_number: fn(u64) -> Duration,
+) -> Result<Duration, D::Error> {
+    match HumanDuration::deserialize(deserializer)? {
+        HumanDuration::Number(count) => Ok(from_number(count)),
+        HumanDuration::Text(text) => humantime::parse_duration(&text)
+            .map_err(|e| D::Error::custom(format!("invalid duration {text:?}: {e}"))),
+    }
+}
+
+/// A `Duration` given as a number of milliseconds or a human-readable string
+pub(crate) mod millis {
+    use std::time::Duration;
+
+    use serde::{Deserializer, Serializer};
+
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    pub(crate) fn serialize<S: Serializer>(
+        duration: &Duration,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        super::serialize(*duration, Duration::from_millis(1), serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Duration, D::Error> {
+        super::deserialize(deserializer, Duration::from_millis)
+    }
+}
+
+/// An optional `Duration` given as a number of seconds or a human-readable
+/// string
+pub(crate) mod optional_secs {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    /// Deserialize through `super::deserialize` inside the `Option`
+    struct Secs(Duration);
+
+    impl<'de> Deserialize<'de> for Secs {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            super::deserialize(deserializer, Duration::from_secs).map(Self)
+        }
+    }
+
+    #[allow(clippy::ref_option)]
+    pub(crate) fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => super::serialize(*duration, Duration::from_secs(1), serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Ok(Option::<Secs>::deserialize(deserializer)?.map(|secs| secs.0))
+    }
+}
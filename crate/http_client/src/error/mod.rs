@@ -26,6 +26,16 @@ pub enum HttpClientError {
     #[error("REST Request Failed: {0}")]
     RequestFailed(String),
 
+    #[error("REST Request Failed with status {code}: {body}")]
+    HttpStatus { code: u16, body: String },
+
+    /// `connect` is set when the timeout was hit while connecting
+    #[error("REST Request Timed Out: {message}")]
+    Timeout { message: String, connect: bool },
+
+    #[error("REST Connection Failed: {0}")]
+    Connection(String),
+
     #[error("REST Response Conversion Failed: {0}")]
     ResponseFailed(String),
 
@@ -33,9 +43,53 @@ pub enum HttpClientError {
     UnexpectedError(String),
 }
 
+impl HttpClientError {
+    /// Whether the request timed out, while connecting or afterwards
+    #[must_use]
+    pub const fn is_timeout(&self) -> bool {
+        matches!(self, Self::Timeout { .. })
+    }
+
+    /// Whether the connection to the server could not be established,
+    /// including when connecting timed out: a connection timeout is both a
+    /// timeout and a connect error
+    #[must_use]
+    pub const fn is_connect(&self) -> bool {
+        matches!(
+            self,
+            Self::Connection(_) | Self::Timeout { connect: true, .. }
+        )
+    }
+
+    /// The status code of the response when the server answered with an
+    /// error status
+    #[must_use]
+    pub const fn status_code(&self) -> Option<u16> {
+        match self {
+            Self::HttpStatus { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+}
+
 impl From<reqwest::Error> for HttpClientError {
     fn from(e: reqwest::Error) -> Self {
-        Self::Default(format!("{e}: Details: {e:?}"))
+        let message = format!("{e}: Details: {e:?}");
+        // a connection timeout is both a timeout and a connect error
+        if e.is_timeout() {
+            Self::Timeout {
+                message,
+                connect: e.is_connect(),
+            }
+        } else if e.is_connect() {
+            Self::Connection(message)
+        } else if e.is_decode() || e.is_body() {
+            Self::ResponseFailed(message)
+        } else if e.is_builder() || e.is_request() || e.is_redirect() || e.is_status() {
+            Self::RequestFailed(message)
+        } else {
+            Self::Default(message)
+        }
     }
 }
 
@@ -53,14 +53,26 @@
     clippy::iter_with_drain
 )]
 
+pub use config_builder::HttpClientConfigBuilder;
 pub use error::HttpClientError;
-pub use http_client::{HttpClient, HttpClientConfig};
+pub use http_client::{
+    CipherPreference, CompressionAlgo, HttpClient, HttpClientConfig, HttpVersionPref,
+    RedirectPolicy, TlsBackend,
+};
 pub use login::{LoginState, Oauth2LoginConfig};
+pub use retry::RetryConfig;
 
 pub mod authentication;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 mod certificate_verifier;
+mod config_builder;
+mod duration;
 mod error;
 mod http_client;
 mod login;
+pub mod request_id;
+mod reqwest_builder;
+mod retry;
 #[cfg(test)]
 pub mod tests;
@@ -0,0 +1,151 @@
+//! Request identifiers, correlating the logs of a request across services.
+//!
+//! On the server side, the [`RequestId`] extractor reads the `X-Request-Id` header of the
+//! incoming request, or generates an identifier if there is none. It is forwarded to the next
+//! services with [`HttpClient::with_request_id`](crate::HttpClient::with_request_id):
+//!
+//! ```rust,no_run
+//! use actix_web::{get, web::Data};
+//! use cosmian_http_client::{HttpClient, request_id::RequestId};
+//!
+//! #[get("/")]
+//! async fn forward(request_id: RequestId, client: Data<HttpClient>) -> String {
+//!     let Ok(client) = client.with_request_id(request_id.as_str()) else {
+//!         return String::new();
+//!     };
+//!     client.get_json("/status").await.unwrap_or_default()
+//! }
+//! ```
+//!
+//! The identifier is recorded as the `request_id` field of the `http.client` span of the
+//! outbound requests and of the span returned by [`RequestId::span`].
+
+use std::{
+    convert::Infallible,
+    fmt::{self, Display},
+    future::{Ready, ready},
+};
+
+use actix_web::{FromRequest, HttpMessage, HttpRequest, dev::Payload};
+use rand::RngCore;
+use tracing::{Span, info_span};
+
+/// The header carrying the request identifier
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// Longer identifiers received from a client are replaced, so that they
+/// cannot flood the logs
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// The identifier of a request, read from the `X-Request-Id` header or
+/// generated.
+///
+/// The identifier is kept in the request extensions, so every extraction
+/// during a request yields the same one.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RequestId(String);
+
+impl RequestId {
+    /// Generate a random identifier of 32 hexadecimal characters.
+    #[must_use]
+    pub fn generate() -> Self {
+        let mut bytes = [0_u8; 16];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        Self(
+            bytes
+                .iter()
+                .flat_map(|byte| [byte >> 4, byte & 0x0f])
+                .filter_map(|digit| char::from_digit(u32::from(digit), 16))
+                .collect(),
+        )
+    }
+
+    /// The identifier of `request`: the one already extracted, the one sent
+    /// by the client if it is valid, or a new one.
+    #[must_use]
+    pub fn of(request: &HttpRequest) -> Self {
+        if let Some(request_id) = request.extensions().get::<Self>() {
+            return request_id.clone();
+        }
+        let request_id = request
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|value| !value.is_empty() && value.len() <= MAX_REQUEST_ID_LENGTH)
+            .map_or_else(Self::generate, |value| Self(value.to_owned()));
+        request.extensions_mut().insert(request_id.clone());
+        request_id
+    }
+
+    /// The identifier as a string.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// A `request` span holding the identifier as its `request_id` field,
+    /// to instrument the handling of the request with.
+    #[must_use]
+    pub fn span(&self) -> Span {
+        info_span!("request", request_id = %self.0)
+    }
+}
+
+impl Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromRequest for RequestId {
+    type Error = Infallible;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(Ok(Self::of(req)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{App, HttpResponse, Responder, get, test};
+
+    use super::{REQUEST_ID_HEADER, RequestId};
+
+    #[get("/request_id")]
+    async fn request_ids(first: RequestId, second: RequestId) -> impl Responder {
+        HttpResponse::Ok().body(format!("{first} {second}"))
+    }
+
+    #[actix_web::test]
+    async fn request_id_extraction() {
+        let app = test::init_service(App::new().service(request_ids)).await;
+
+        let request = test::TestRequest::get()
+            .uri("/request_id")
+            .insert_header((REQUEST_ID_HEADER, "abc-123"))
+            .to_request();
+        let body = test::call_and_read_body(&app, request).await;
+        assert_eq!(body, "abc-123 abc-123");
+
+        // generated once per request, when missing or invalid
+        let too_long = "x".repeat(129);
+        for value in [None, Some(""), Some(too_long.as_str())] {
+            let mut request = test::TestRequest::get().uri("/request_id");
+            if let Some(value) = value {
+                request = request.insert_header((REQUEST_ID_HEADER, value));
+            }
+            let body = test::call_and_read_body(&app, request.to_request()).await;
+            let body = String::from_utf8_lossy(&body);
+            let ids = body.split(' ').collect::<Vec<_>>();
+            assert_eq!(ids.len(), 2, "Failed for {value:?}");
+            assert_eq!(ids.first(), ids.get(1), "Failed for {value:?}");
+            assert!(
+                ids.first().is_some_and(|id| id.len() == 32),
+                "Failed for {value:?}"
+            );
+        }
+        assert_ne!(RequestId::generate(), RequestId::generate());
+    }
+}
@@ -0,0 +1,136 @@
+use std::{net::SocketAddr, time::Duration};
+
+use reqwest::{
+    Certificate, Identity, Proxy,
+    header::{HeaderMap, HeaderValue},
+    redirect::Policy,
+};
+
+/// The settings shared by the async and the blocking `reqwest` client
+/// builders, so that both clients are set up from the same configuration by
+/// the same code.
+pub(crate) trait ReqwestBuilder: Sized {
+    fn new() -> Self;
+    fn danger_accept_invalid_certs(self, accept_invalid_certs: bool) -> Self;
+    fn add_root_certificate(self, certificate: Certificate) -> Self;
+    fn identity(self, identity: Identity) -> Self;
+    fn use_preconfigured_tls(self, config: rustls::ClientConfig) -> Self;
+    fn user_agent(self, user_agent: HeaderValue) -> Self;
+    fn default_headers(self, headers: HeaderMap) -> Self;
+    fn timeout(self, timeout: Duration) -> Self;
+    fn connect_timeout(self, timeout: Duration) -> Self;
+    fn redirect(self, policy: Policy) -> Self;
+    fn pool_max_idle_per_host(self, max_idle: usize) -> Self;
+    fn pool_idle_timeout(self, timeout: Duration) -> Self;
+    fn tcp_keepalive(self, interval: Duration) -> Self;
+    #[cfg(feature = "compression")]
+    fn gzip(self, enable: bool) -> Self;
+    #[cfg(feature = "compression")]
+    fn brotli(self, enable: bool) -> Self;
+    #[cfg(feature = "compression")]
+    fn deflate(self, enable: bool) -> Self;
+    fn http1_only(self) -> Self;
+    fn http2_prior_knowledge(self) -> Self;
+    fn resolve(self, domain: &str, address: SocketAddr) -> Self;
+    fn proxy(self, proxy: Proxy) -> Self;
+    fn no_proxy(self) -> Self;
+}
+
+/// Implement `ReqwestBuilder` by forwarding to the inherent methods of the
+/// builder, which take precedence over the trait ones.
+macro_rules! impl_reqwest_builder {
+    ($builder:ty) => {
+        impl ReqwestBuilder for $builder {
+            fn new() -> Self {
+                Self::new()
+            }
+
+            fn danger_accept_invalid_certs(self, accept_invalid_certs: bool) -> Self {
+                self.danger_accept_invalid_certs(accept_invalid_certs)
+            }
+
+            fn add_root_certificate(self, certificate: Certificate) -> Self {
+                self.add_root_certificate(certificate)
+            }
+
+            fn identity(self, identity: Identity) -> Self {
+                self.identity(identity)
+            }
+
+            fn use_preconfigured_tls(self, config: rustls::ClientConfig) -> Self {
+                self.use_preconfigured_tls(config)
+            }
+
+            fn user_agent(self, user_agent: HeaderValue) -> Self {
+                self.user_agent(user_agent)
+            }
+
+            fn default_headers(self, headers: HeaderMap) -> Self {
+                self.default_headers(headers)
+            }
+
+            fn timeout(self, timeout: Duration) -> Self {
+                self.timeout(timeout)
+            }
+
+            fn connect_timeout(self, timeout: Duration) -> Self {
+                self.connect_timeout(timeout)
+            }
+
+            fn redirect(self, policy: Policy) -> Self {
+                self.redirect(policy)
+            }
+
+            fn pool_max_idle_per_host(self, max_idle: usize) -> Self {
+                self.pool_max_idle_per_host(max_idle)
+            }
+
+            fn pool_idle_timeout(self, timeout: Duration) -> Self {
+                self.pool_idle_timeout(timeout)
+            }
+
+            fn tcp_keepalive(self, interval: Duration) -> Self {
+                self.tcp_keepalive(interval)
+            }
+
+            #[cfg(feature = "compression")]
+            fn gzip(self, enable: bool) -> Self {
+                self.gzip(enable)
+            }
+
+            #[cfg(feature = "compression")]
+            fn brotli(self, enable: bool) -> Self {
+                self.brotli(enable)
+            }
+
+            #[cfg(feature = "compression")]
+            fn deflate(self, enable: bool) -> Self {
+                self.deflate(enable)
+            }
+
+            fn http1_only(self) -> Self {
+                self.http1_only()
+            }
+
+            fn http2_prior_knowledge(self) -> Self {
+                self.http2_prior_knowledge()
+            }
+
+            fn resolve(self, domain: &str, address: SocketAddr) -> Self {
+                self.resolve(domain, address)
+            }
+
+            fn proxy(self, proxy: Proxy) -> Self {
+                self.proxy(proxy)
+            }
+
+            fn no_proxy(self) -> Self {
+                self.no_proxy()
+            }
+        }
+    };
+}
+
+impl_reqwest_builder!(reqwest::ClientBuilder);
+#[cfg(feature = "blocking")]
+impl_reqwest_builder!(reqwest::blocking::ClientBuilder);
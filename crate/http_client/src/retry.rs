@@ -0,0 +1,165 @@
+use std::time::{Duration, SystemTime};
+
+use reqwest::{RequestBuilder, Response, header::RETRY_AFTER};
+use serde::{Deserialize, Serialize};
+
+use crate::{HttpClientError, error::result::HttpClientResult};
+
+/// How failed requests are retried
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// The maximum number of retries after the first attempt
+    pub max_retries: u32,
+    /// The delay before the first retry, doubled on each retry, in
+    /// milliseconds or as a duration such as `250ms`
+    #[serde(with = "crate::duration::millis")]
+    pub base_delay: Duration,
+    /// The maximum delay between two attempts, in milliseconds or as a
+    /// duration such as `10s`
+    #[serde(with = "crate::duration::millis")]
+    pub max_delay: Duration,
+    /// The response status codes that trigger a retry; connection errors and
+    /// timeouts are always retried
+    pub retryable_status_codes: Vec<u16>,
+    /// Also retry requests that are not idempotent, such as POST
+    pub retry_non_idempotent: bool,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            retryable_status_codes: vec![429, 502, 503, 504],
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// The delay before the retry following `attempt` (starting at 0)
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2_u32.saturating_pow(attempt))
+            .min(self.max_delay)
+    }
+
+    /// Send a request, retrying it on connection errors, timeouts and
+    /// retryable status codes.
+    ///
+    /// The delay between two attempts grows exponentially, unless the server
+    /// sends a `Retry-After` header with a number of seconds or a date.
+    /// Requests that are not idempotent are sent once unless `retry_non_idempotent` is set.
+    ///
+    /// When all attempts failed, the last response is returned if there was
+    /// one, or the last error as a `HttpClientError::Timeout` or
+    /// `HttpClientError::Connection`.
+    pub(crate) async fn send(
+        &self,
+        request: RequestBuilder,
+        idempotent: bool,
+    ) -> HttpClientResult<Response> {
+        if !idempotent && !self.retry_non_idempotent {
+            return Ok(request.send().await?);
+        }
+
+        let mut attempt = 0;
+        loop {
+            // requests with a streamed body cannot be cloned, send them once
+            let Some(attempt_request) = request.try_clone() else {
+                return Ok(request.send().await?);
+            };
+            let result = attempt_request.send().await;
+            let delay = match &result {
+                Ok(response)
+                    if self
+                        .retryable_status_codes
+                        .contains(&response.status().as_u16()) =>
+                {
+                    retry_after(response)
+                        .map_or_else(|| self.backoff(attempt), |delay| delay.min(self.max_delay))
+                }
+                Err(e) if e.is_connect() || e.is_timeout() => self.backoff(attempt),
+                _ => return Ok(result?),
+            };
+            if attempt >= self.max_retries {
+                return result.map_err(|e| {
+                    let message = format!("giving up after {} attempts: {e}", attempt + 1);
+                    if e.is_timeout() {
+                        HttpClientError::Timeout {
+                            message,
+                            connect: e.is_connect(),
+                        }
+                    } else {
+                        HttpClientError::Connection(message)
+                    }
+                });
+            }
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// The delay requested by the server in a `Retry-After` header
+fn retry_after(response: &Response) -> Option<Duration> {
+    parse_retry_after(
+        response.headers().get(RETRY_AFTER)?.to_str().ok()?,
+        SystemTime::now(),
+    )
+}
+
+/// Parse a `Retry-After` value, either a number of seconds or an HTTP date,
+/// into a delay from `now`; a date in the past means no delay
+fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(now).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::{RetryConfig, parse_retry_after};
+
+    #[test]
+    fn backoff() {
+        let retry = RetryConfig {
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(500),
+            ..Default::default()
+        };
+        assert_eq!(retry.backoff(0), Duration::from_millis(100));
+        assert_eq!(retry.backoff(1), Duration::from_millis(200));
+        assert_eq!(retry.backoff(2), Duration::from_millis(400));
+        assert_eq!(retry.backoff(3), Duration::from_millis(500));
+        assert_eq!(retry.backoff(100), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn retry_after() {
+        // Wed, 21 Oct 2015 07:28:00 GMT
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_445_412_480);
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(parse_retry_after(" 0 ", now), Some(Duration::ZERO));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:30:00 GMT", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("-1", now), None);
+        assert_eq!(parse_retry_after("tomorrow", now), None);
+    }
+}
@@ -1,2 +1,3 @@
 #[cfg(feature = "session")]
 pub mod session_store;
+pub mod test_server;
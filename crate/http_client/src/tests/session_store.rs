@@ -6,9 +6,11 @@ use actix_web::cookie::time::Duration;
 use anyhow::Error;
 
 /// A simple in-memory session store for testing purposes.
+///
+/// Deleted sessions leave an empty slot so that their keys are never reused.
 #[derive(Default)]
 pub struct MockSessionStore {
-    data: RefCell<Vec<HashMap<String, String>>>,
+    data: RefCell<Vec<Option<HashMap<String, String>>>>,
 }
 
 /// Convert a session key to an index into the data store.
@@ -22,7 +24,7 @@ impl SessionStore for MockSessionStore {
         session_key: &SessionKey,
     ) -> Result<Option<HashMap<String, String>>, LoadError> {
         key_to_index(session_key)
-            .map(|i| self.data.borrow().get(i).cloned())
+            .map(|i| self.data.borrow().get(i).cloned().flatten())
             .map_err(LoadError::Deserialization)
     }
 
@@ -35,7 +37,7 @@ impl SessionStore for MockSessionStore {
             .map_err(Error::from)
             .map_err(SaveError::Serialization)?;
 
-        self.data.borrow_mut().push(session_state);
+        self.data.borrow_mut().push(Some(session_state));
 
         Ok(key)
     }
@@ -49,14 +51,18 @@ impl SessionStore for MockSessionStore {
         key_to_index(&session_key)
             .map_err(UpdateError::Other)
             .and_then(|i| {
-                self.data.borrow_mut().get_mut(i).map_or_else(
-                    || Err(UpdateError::Other(Error::msg("No such key"))),
-                    |data| {
-                        *data = session_state;
+                self.data
+                    .borrow_mut()
+                    .get_mut(i)
+                    .and_then(Option::as_mut)
+                    .map_or_else(
+                        || Err(UpdateError::Other(Error::msg("No such key"))),
+                        |data| {
+                            *data = session_state;
 
-                        Ok(session_key)
-                    },
-                )
+                            Ok(session_key)
+                        },
+                    )
             })
     }
 
@@ -65,9 +71,9 @@ impl SessionStore for MockSessionStore {
     }
 
     async fn delete(&self, session_key: &SessionKey) -> Result<(), Error> {
-        match key_to_index(session_key)? {
-            i if i < self.data.borrow().len() => {
-                self.data.borrow_mut().remove(i);
+        match self.data.borrow_mut().get_mut(key_to_index(session_key)?) {
+            Some(data) if data.is_some() => {
+                *data = None;
 
                 Ok(())
             }
@@ -0,0 +1,128 @@
+use std::{
+    io::{self, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::mpsc::{self, Receiver},
+};
+
+use actix_web::{App, HttpServer, web::ServiceConfig};
+use anyhow::{Error, anyhow};
+
+/// Start an HTTP/1.1 and cleartext HTTP/2 server on a random local port,
+/// serving the routes set up by `configure`, and return its address.
+///
+/// The server runs until the end of the test runtime.
+///
+/// # Errors
+/// Returns an error if the server cannot be bound to a local port.
+pub fn spawn_test_server(configure: fn(&mut ServiceConfig)) -> Result<SocketAddr, Error> {
+    let server =
+        HttpServer::new(move || App::new().configure(configure)).bind_auto_h2c(("127.0.0.1", 0))?;
+    let address = server
+        .addrs()
+        .first()
+        .copied()
+        .ok_or_else(|| anyhow!("test server is not bound"))?;
+    actix_web::rt::spawn(server.workers(1).run());
+    Ok(address)
+}
+
+/// Start a SOCKS5 proxy on a random local port and return its address.
+///
+/// The proxy accepts a single connection, authenticated with the `username`
+/// and `password` credentials, and forwards it to `target` whatever the
+/// requested destination. The destination requested by the client, `host:port`, is sent to the
+/// returned receiver, a hostname proving that the proxy resolves it.
+///
+/// # Errors
+/// Returns an error if the proxy cannot be bound to a local port.
+pub fn spawn_socks5_proxy(
+    username: &'static str,
+    password: &'static str,
+    target: SocketAddr,
+) -> Result<(SocketAddr, Receiver<String>), Error> {
+    let listener = TcpListener::bind(("127.0.0.1", 0))?;
+    let address = listener.local_addr()?;
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let Ok((mut client, _)) = listener.accept() else {
+            return;
+        };
+        let Ok(destination) = socks5_handshake(&mut client, username, password) else {
+            return;
+        };
+        drop(sender.send(destination));
+        let (Ok(server), Ok(client_reader)) = (TcpStream::connect(target), client.try_clone())
+        else {
+            return;
+        };
+        let Ok(server_writer) = server.try_clone() else {
+            return;
+        };
+        std::thread::spawn(move || forward(client_reader, server_writer));
+        forward(server, client);
+    });
+    Ok((address, receiver))
+}
+
+/// Run the server side of a SOCKS5 CONNECT with username and password
+/// authentication (RFC 1928 and RFC 1929), and return the destination
+fn socks5_handshake(client: &mut TcpStream, username: &str, password: &str) -> io::Result<String> {
+    let refused = |message: &str| io::Error::new(io::ErrorKind::PermissionDenied, message);
+    let read_bytes = |client: &mut TcpStream, length: usize| {
+        let mut bytes = vec![0; length];
+        client.read_exact(&mut bytes).map(|()| bytes)
+    };
+    let read_u8 = |client: &mut TcpStream| {
+        let mut byte = [0];
+        client.read_exact(&mut byte).map(|()| byte[0])
+    };
+
+    // version, then the authentication methods
+    let mut header = [0; 2];
+    client.read_exact(&mut header)?;
+    let methods = read_bytes(client, usize::from(header[1]))?;
+    if header[0] != 5 || !methods.contains(&2) {
+        client.write_all(&[5, 0xff])?;
+        return Err(refused("username and password authentication not offered"));
+    }
+    client.write_all(&[5, 2])?;
+
+    // version 1 of the username and password sub-negotiation
+    read_u8(client)?;
+    let length = usize::from(read_u8(client)?);
+    let received_username = read_bytes(client, length)?;
+    let length = usize::from(read_u8(client)?);
+    let received_password = read_bytes(client, length)?;
+    if received_username != username.as_bytes() || received_password != password.as_bytes() {
+        client.write_all(&[1, 1])?;
+        return Err(refused("invalid credentials"));
+    }
+    client.write_all(&[1, 0])?;
+
+    // version, CONNECT, reserved, then the address type and the address
+    let mut request = [0; 4];
+    client.read_exact(&mut request)?;
+    let host = match request[3] {
+        1 => read_bytes(client, 4)?
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("."),
+        3 => {
+            let length = usize::from(read_u8(client)?);
+            String::from_utf8_lossy(&read_bytes(client, length)?).into_owned()
+        }
+        _ => return Err(refused("unsupported address type")),
+    };
+    let mut port = [0; 2];
+    client.read_exact(&mut port)?;
+    let port = u16::from_be_bytes(port);
+    client.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0])?;
+    Ok(format!("{host}:{port}"))
+}
+
+/// Copy everything read from `from` to `to`, until either is closed
+fn forward(mut from: TcpStream, mut to: TcpStream) {
+    drop(io::copy(&mut from, &mut to));
+    drop(to.shutdown(std::net::Shutdown::Write));
+}
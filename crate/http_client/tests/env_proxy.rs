@@ -0,0 +1,95 @@
+//! reqwest reads the proxy environment variables once per process, so this
+//! test runs in its own binary.
+
+use std::net::SocketAddr;
+
+use actix_web::{App, HttpServer, web};
+use cosmian_http_client::{HttpClient, HttpClientConfig};
+
+/// Start a server answering `name` to any request, be it a proxy or not,
+/// and return its address
+fn spawn_server(name: &'static str) -> Result<SocketAddr, anyhow::Error> {
+    let server =
+        HttpServer::new(move || App::new().default_service(web::to(move || async move { name })))
+            .bind(("127.0.0.1", 0))?;
+    let address = server
+        .addrs()
+        .first()
+        .copied()
+        .ok_or_else(|| anyhow::anyhow!("server is not bound"))?;
+    actix_web::rt::spawn(server.workers(1).run());
+    Ok(address)
+}
+
+/// Send a GET request to `server` with a client built from `http_conf` and
+/// return the name of the server, or proxy, which answered
+async fn answered_by(
+    http_conf: HttpClientConfig,
+    server: SocketAddr,
+) -> Result<String, anyhow::Error> {
+    let http_client = HttpClient::instantiate(&http_conf)?;
+    Ok(http_client
+        .client()
+        .get(format!("http://{server}/"))
+        .send()
+        .await?
+        .text()
+        .await?)
+}
+
+#[actix_web::test]
+async fn env_proxy_precedence() -> Result<(), anyhow::Error> {
+    let direct = spawn_server("direct")?;
+    let env_proxy = spawn_server("env proxy")?;
+    let proxy = spawn_server("proxy")?;
+    for name in [
+        "http_proxy",
+        "HTTPS_PROXY",
+        "https_proxy",
+        "ALL_PROXY",
+        "all_proxy",
+        "NO_PROXY",
+        "no_proxy",
+        "REQUEST_METHOD",
+    ] {
+        std::env::remove_var(name);
+    }
+    std::env::set_var("HTTP_PROXY", format!("http://{env_proxy}"));
+
+    // the environment proxy is used by default
+    let http_conf = HttpClientConfig::default();
+    assert_eq!(answered_by(http_conf.clone(), direct).await?, "env proxy");
+
+    // unless disabled
+    let http_conf_without_env = HttpClientConfig {
+        use_env_proxy: false,
+        ..http_conf.clone()
+    };
+    assert_eq!(
+        answered_by(http_conf_without_env.clone(), direct).await?,
+        "direct"
+    );
+
+    // an explicit proxy always takes precedence
+    for http_conf in [http_conf, http_conf_without_env] {
+        let http_conf = HttpClientConfig {
+            proxy: Some(format!("http://{proxy}")),
+            ..http_conf
+        };
+        assert_eq!(answered_by(http_conf, direct).await?, "proxy");
+    }
+
+    // NO_PROXY excludes hosts from the explicit proxy too, unless the
+    // environment is ignored
+    std::env::set_var("NO_PROXY", "example.com, 127.0.0.0/8");
+    for (use_env_proxy, expected) in [(true, "direct"), (false, "proxy")] {
+        let http_conf = HttpClientConfig {
+            proxy: Some(format!("http://{proxy}")),
+            use_env_proxy,
+            ..Default::default()
+        };
+        assert_eq!(answered_by(http_conf, direct).await?, expected);
+    }
+
+    Ok(())
+}
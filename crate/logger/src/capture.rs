@@ -0,0 +1,153 @@
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use tracing::{
+    Event, Level, Subscriber,
+    field::{Field, Visit},
+    subscriber::DefaultGuard,
+};
+use tracing_subscriber::{Layer, layer::Context, layer::SubscriberExt, registry};
+
+/// An event recorded by `log_init_capture`
+#[derive(Clone, Debug)]
+pub struct CapturedEvent {
+    pub level: Level,
+    pub target: String,
+    /// The message followed by the other fields, as `name=value`
+    pub message: String,
+}
+
+/// The events captured since `log_init_capture` was called.
+///
+/// The capture stops when this is dropped.
+pub struct CapturedLogs {
+    events: Arc<Mutex<Vec<CapturedEvent>>>,
+    _guard: DefaultGuard,
+}
+
+impl CapturedLogs {
+    /// A copy of the events captured so far
+    #[must_use]
+    pub fn events(&self) -> Vec<CapturedEvent> {
+        self.events
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Whether an event of `level` with a message containing `substring`
+    /// was captured
+    #[must_use]
+    pub fn contains(&self, level: Level, substring: &str) -> bool {
+        self.events
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .any(|event| event.level == level && event.message.contains(substring))
+    }
+}
+
+/// Collects the message and the fields of an event
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: Vec<String>,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        } else {
+            self.fields.push(format!("{}={value:?}", field.name()));
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            value.clone_into(&mut self.message);
+        } else {
+            self.fields.push(format!("{}={value}", field.name()));
+        }
+    }
+}
+
+struct CaptureLayer {
+    events: Arc<Mutex<Vec<CapturedEvent>>>,
+}
+
+impl<S: Subscriber> Layer<S> for CaptureLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let mut message = visitor.message;
+        for field in visitor.fields {
+            if !message.is_empty() {
+                message.push(' ');
+            }
+            message.push_str(&field);
+        }
+        self.events
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(CapturedEvent {
+                level: *event.metadata().level(),
+                target: event.metadata().target().to_owned(),
+                message,
+            });
+    }
+}
+
+/// Capture in memory the events emitted on the current thread, at every
+/// level, so that tests can assert that a given log line was produced.
+///
+/// The capturing subscriber is the default of the current thread only, until
+/// the returned `CapturedLogs` is dropped: tests running in parallel do not
+/// see each other's events, and the global subscriber installed by
+/// `log_init`, if any, does not receive them meanwhile. Events emitted by
+/// other threads, e.g. by a multi-threaded async runtime, are not captured.
+#[must_use]
+pub fn log_init_capture() -> CapturedLogs {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let subscriber = registry().with(CaptureLayer {
+        events: Arc::clone(&events),
+    });
+    CapturedLogs {
+        events,
+        _guard: tracing::subscriber::set_default(subscriber),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing::{Level, debug, info};
+
+    use super::log_init_capture;
+
+    #[test]
+    fn capture() {
+        let logs = log_init_capture();
+
+        info!(user = "alice", "user logged in");
+        debug!("cache miss");
+        std::thread::spawn(|| info!("from another thread"))
+            .join()
+            .unwrap_or_default();
+
+        assert!(logs.contains(Level::INFO, "user logged in"));
+        // the fields follow the message
+        assert!(logs.contains(Level::INFO, "user logged in user=alice"));
+        // every level is captured, and matched by `contains`
+        assert!(logs.contains(Level::DEBUG, "cache miss"));
+        assert!(!logs.contains(Level::INFO, "cache miss"));
+        // only the current thread is captured
+        assert!(!logs.contains(Level::INFO, "from another thread"));
+        assert_eq!(logs.events().len(), 2);
+
+        drop(logs);
+        let logs = log_init_capture();
+        assert!(logs.events().is_empty());
+    }
+}
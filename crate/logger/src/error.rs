@@ -0,0 +1,13 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum LoggerError {
+    #[error("Logger not initialized: {0}")]
+    NotInitialized(String),
+
+    #[error("Parsing error: {0}")]
+    Parsing(String),
+
+    #[error("Reload error: {0}")]
+    Reload(String),
+}
@@ -1,6 +1,15 @@
+#[cfg(feature = "capture")]
+mod capture;
+mod error;
 mod log_utils;
 
-pub use log_utils::log_init;
+#[cfg(feature = "capture")]
+pub use capture::{CapturedEvent, CapturedLogs, log_init_capture};
+pub use error::LoggerError;
+pub use log_utils::{
+    BacktraceMode, current_log_filter, log_init, log_init_with_backtrace, log_init_with_directives,
+    set_log_filter,
+};
 pub mod reexport {
     pub use tracing;
     pub use tracing_subscriber;
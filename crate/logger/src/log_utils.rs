@@ -1,39 +1,116 @@
 use std::{
     env::{set_var, var},
-    sync::Once,
+    sync::{Once, OnceLock},
 };
 
 use tracing_subscriber::{
-    EnvFilter, layer::SubscriberExt, registry, reload, util::SubscriberInitExt,
+    EnvFilter, Registry, layer::SubscriberExt, registry, reload, util::SubscriberInitExt,
 };
 
+use crate::LoggerError;
+
 static LOG_INIT: Once = Once::new();
 
+/// Handle on the filter layer installed by `log_init`, used to change the
+/// filter at runtime
+static RELOAD_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// How `log_init_with_backtrace` sets the `RUST_BACKTRACE` environment
+/// variable
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum BacktraceMode {
+    /// `RUST_BACKTRACE=0`: no backtrace on panic
+    Off,
+    /// `RUST_BACKTRACE=1`
+    Short,
+    /// `RUST_BACKTRACE=full`, what `log_init` does
+    #[default]
+    Full,
+    /// `RUST_BACKTRACE` is left untouched
+    Leave,
+}
+
+impl BacktraceMode {
+    const fn value(self) -> Option<&'static str> {
+        match self {
+            Self::Off => Some("0"),
+            Self::Short => Some("1"),
+            Self::Full => Some("full"),
+            Self::Leave => None,
+        }
+    }
+}
+
+/// Initialize the global tracing subscriber, once per process.
+///
+/// Records emitted by dependencies through the `log` crate facade are
+/// routed to the same subscriber and filter.
+///
+/// This also sets `RUST_BACKTRACE=full`, see `log_init_with_backtrace` to
+/// change it.
+///
 /// # Panics
 ///
 /// Will panic if we cannot set global tracing subscriber
 pub fn log_init(default_value: Option<&str>) {
+    log_init_with_backtrace(default_value, BacktraceMode::Full);
+}
+
+/// Same as `log_init`, setting `RUST_BACKTRACE` as told by `backtrace`.
+///
+/// # Panics
+///
+/// Will panic if we cannot set global tracing subscriber
+pub fn log_init_with_backtrace(default_value: Option<&str>, backtrace: BacktraceMode) {
     if default_value.is_some() || var("RUST_LOG").is_ok() {
         LOG_INIT.call_once(|| unsafe {
             if let Ok(current_value) = var("RUST_LOG") {
                 set_var("RUST_LOG", current_value);
-                set_var("RUST_BACKTRACE", "full");
-                tracing_setup();
             } else if let Some(input_value) = default_value {
                 set_var("RUST_LOG", input_value);
-                set_var("RUST_BACKTRACE", "full");
-                tracing_setup();
             }
+            if let Some(value) = backtrace.value() {
+                set_var("RUST_BACKTRACE", value);
+            }
+            tracing_setup(EnvFilter::from_default_env());
         });
     }
 }
 
+/// Initialize the global tracing subscriber, once per process, from a list of
+/// filter directives (e.g. `["info", "my_crate=debug"]`) without touching
+/// the `RUST_LOG` or `RUST_BACKTRACE` environment variables.
+///
+/// The directives are added on top of the filter read from `RUST_LOG`, if
+/// any: a directive for the same target replaces the `RUST_LOG` one, so the
+/// directives take precedence.
+///
+/// Like `log_init`, this is a no-op if the subscriber is already installed.
+///
+/// # Errors
+///
+/// Will return an error if a directive cannot be parsed
+///
+/// # Panics
+///
+/// Will panic if we cannot set global tracing subscriber
+pub fn log_init_with_directives(directives: &[&str]) -> Result<(), LoggerError> {
+    let mut filter = EnvFilter::from_default_env();
+    for directive in directives {
+        filter = filter.add_directive(directive.parse().map_err(|e| {
+            LoggerError::Parsing(format!("invalid filter directive {directive:?}: {e}"))
+        })?);
+    }
+    LOG_INIT.call_once(|| tracing_setup(filter));
+    Ok(())
+}
+
 /// # Panics
 ///
 /// Will panic if:
 /// - we cannot set global subscriber
 /// - we cannot init the log tracer
-fn tracing_setup() {
+fn tracing_setup(env_filter: EnvFilter) {
     let format = tracing_subscriber::fmt::layer()
         .with_level(true)
         .with_target(true)
@@ -43,7 +120,58 @@ fn tracing_setup() {
         .with_ansi(true)
         .compact();
 
-    let (filter, _reload_handle) = reload::Layer::new(EnvFilter::from_default_env());
+    let (filter, reload_handle) = reload::Layer::new(env_filter);
 
     registry().with(filter).with(format).init();
+
+    let _ = RELOAD_HANDLE.set(reload_handle);
+}
+
+/// Replace the filter installed by `log_init` (or `log_init_with_directives`)
+/// with one built from the given
+/// directives (same syntax as `RUST_LOG`, e.g. `info,my_crate=debug`).
+///
+/// # Errors
+///
+/// Will return an error if:
+/// - `log_init` has not installed the tracing subscriber yet
+/// - the directives cannot be parsed, in which case the previous filter is kept
+/// - the filter layer cannot be reloaded
+pub fn set_log_filter(directives: &str) -> Result<(), LoggerError> {
+    let reload_handle = RELOAD_HANDLE
+        .get()
+        .ok_or_else(|| LoggerError::NotInitialized("log_init has not been called".to_owned()))?;
+    let filter = EnvFilter::try_new(directives)
+        .map_err(|e| LoggerError::Parsing(format!("invalid filter directives: {e}")))?;
+    reload_handle
+        .reload(filter)
+        .map_err(|e| LoggerError::Reload(e.to_string()))
+}
+
+/// The directives of the filter currently in effect, as installed by
+/// `log_init` or `log_init_with_directives` and changed by `set_log_filter`,
+/// e.g. `my_crate=debug,info`.
+///
+/// Returns `None` if the tracing subscriber is not installed yet.
+#[must_use]
+pub fn current_log_filter() -> Option<String> {
+    RELOAD_HANDLE.get()?.with_current(ToString::to_string).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BacktraceMode;
+
+    #[test]
+    fn backtrace_values() {
+        for (mode, expected) in [
+            (BacktraceMode::Off, Some("0")),
+            (BacktraceMode::Short, Some("1")),
+            (BacktraceMode::Full, Some("full")),
+            (BacktraceMode::Leave, None),
+        ] {
+            assert_eq!(mode.value(), expected, "Failed for {mode:?}");
+        }
+        assert_eq!(BacktraceMode::default(), BacktraceMode::Full);
+    }
 }
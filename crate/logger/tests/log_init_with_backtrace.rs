@@ -0,0 +1,16 @@
+//! The tracing subscriber is global, so this test runs in its own binary.
+
+use cosmian_logger::{BacktraceMode, log_init_with_backtrace};
+
+#[test]
+fn backtrace_is_set() {
+    std::env::remove_var("RUST_LOG");
+    std::env::set_var("RUST_BACKTRACE", "full");
+
+    log_init_with_backtrace(Some("info"), BacktraceMode::Short);
+    assert_eq!(std::env::var("RUST_BACKTRACE").as_deref(), Ok("1"));
+
+    // the logger is initialized once, later calls change nothing
+    log_init_with_backtrace(Some("info"), BacktraceMode::Off);
+    assert_eq!(std::env::var("RUST_BACKTRACE").as_deref(), Ok("1"));
+}
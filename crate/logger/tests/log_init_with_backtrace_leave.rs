@@ -0,0 +1,14 @@
+//! The tracing subscriber is global, so this test runs in its own binary.
+
+use cosmian_logger::{BacktraceMode, log_init_with_backtrace};
+use tracing::{Level, enabled};
+
+#[test]
+fn backtrace_is_left_untouched() {
+    std::env::remove_var("RUST_LOG");
+    std::env::set_var("RUST_BACKTRACE", "0");
+
+    log_init_with_backtrace(Some("info"), BacktraceMode::Leave);
+    assert!(enabled!(Level::INFO));
+    assert_eq!(std::env::var("RUST_BACKTRACE").as_deref(), Ok("0"));
+}
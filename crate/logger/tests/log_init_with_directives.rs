@@ -0,0 +1,24 @@
+//! The tracing subscriber is global, so this test runs in its own binary.
+
+use cosmian_logger::{LoggerError, current_log_filter, log_init_with_directives};
+use tracing::{Level, enabled};
+
+#[test]
+fn directives_over_rust_log() {
+    std::env::set_var("RUST_LOG", "info,my_crate=warn");
+
+    // nothing is installed on error
+    assert!(matches!(
+        log_init_with_directives(&["not a level=oops"]),
+        Err(LoggerError::Parsing(_))
+    ));
+    assert_eq!(current_log_filter(), None);
+
+    log_init_with_directives(&["my_crate=debug", "other_crate=error"]).unwrap();
+    // `my_crate=debug` replaces the `RUST_LOG` directive for the same target
+    assert!(enabled!(target: "my_crate", Level::DEBUG));
+    assert!(!enabled!(target: "other_crate", Level::WARN));
+    // the other `RUST_LOG` directives are kept
+    assert!(enabled!(target: "third_crate", Level::INFO));
+    assert!(!enabled!(target: "third_crate", Level::DEBUG));
+}
@@ -0,0 +1,34 @@
+//! The tracing subscriber is global, so this test runs in its own binary.
+
+use cosmian_logger::{LoggerError, current_log_filter, log_init, set_log_filter};
+use tracing::{Level, enabled};
+
+#[test]
+fn set_log_filter_at_runtime() {
+    assert!(matches!(
+        set_log_filter("debug"),
+        Err(LoggerError::NotInitialized(_))
+    ));
+    assert_eq!(current_log_filter(), None);
+
+    std::env::remove_var("RUST_LOG");
+    log_init(Some("info"));
+    assert!(enabled!(Level::INFO));
+    assert!(!enabled!(Level::DEBUG));
+
+    assert_eq!(current_log_filter().as_deref(), Some("info"));
+
+    set_log_filter("debug").unwrap();
+    assert_eq!(current_log_filter().as_deref(), Some("debug"));
+    assert!(enabled!(Level::DEBUG));
+    assert!(!enabled!(Level::TRACE));
+
+    // an invalid directive keeps the active filter
+    assert!(matches!(
+        set_log_filter("not a level=oops"),
+        Err(LoggerError::Parsing(_))
+    ));
+    assert!(enabled!(Level::DEBUG));
+    assert!(!enabled!(Level::TRACE));
+    assert_eq!(current_log_filter().as_deref(), Some("debug"));
+}